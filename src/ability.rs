@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// One of the six abilities that every creature has a score in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ability {
+    Strength,
+    Dexterity,
+    Constitution,
+    Intelligence,
+    Wisdom,
+    Charisma,
+}
+
+impl Ability {
+    /// All abilities, in the order they appear in a stat block.
+    pub const ALL: [Ability; 6] = [
+        Ability::Strength,
+        Ability::Dexterity,
+        Ability::Constitution,
+        Ability::Intelligence,
+        Ability::Wisdom,
+        Ability::Charisma,
+    ];
+
+    /// Parses an ability from its name or abbreviation, ignoring case.
+    pub fn parse(name: &str) -> Option<Ability> {
+        let name = name.trim().to_ascii_lowercase();
+        Ability::ALL.into_iter().find(|ability| {
+            let abbreviation = ability.abbreviation().to_ascii_lowercase();
+            name == abbreviation || name == format!("{:?}", ability).to_ascii_lowercase()
+        })
+    }
+
+    /// Returns the three-letter abbreviation of the ability, e.g. "Str".
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Ability::Strength => "Str",
+            Ability::Dexterity => "Dex",
+            Ability::Constitution => "Con",
+            Ability::Intelligence => "Int",
+            Ability::Wisdom => "Wis",
+            Ability::Charisma => "Cha",
+        }
+    }
+}
+
+/// Returns the modifier for the given ability score, e.g. +2 for a score of 14 or 15.
+pub fn modifier(score: i32) -> i32 {
+    (score - 10).div_euclid(2)
+}
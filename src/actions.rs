@@ -1,16 +1,26 @@
+use serde::{Deserialize, Serialize};
+
 /// The different actions available for a combatant and whether they are available.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Actions {
     /// The actions available for the combatant.
-    pub actions: Vec<Action>,
+    pub actions: Vec<ActionSlot>,
+
+    /// The legendary actions of a legendary creature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legendary: Option<LegendaryActions>,
 }
 
 impl std::fmt::Display for Actions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some((last, elements)) = self.actions.split_last() {
-            for action in elements {
-                write!(f, "{}/", action)?;
+            for slot in elements {
+                write!(f, "{}/", slot.action)?;
             }
-            write!(f, "{}", last)?;
+            write!(f, "{}", last.action)?;
+        }
+        if let Some(legendary) = self.legendary {
+            write!(f, "/L{}", legendary.left())?;
         }
 
         Ok(())
@@ -21,7 +31,11 @@ impl Default for Actions {
     fn default() -> Actions {
         Actions {
             // not all creatures have bonus actions
-            actions: vec![Action::Move, Action::Action, Action::Reaction],
+            actions: [Action::Move, Action::Action, Action::Reaction]
+                .into_iter()
+                .map(ActionSlot::new)
+                .collect(),
+            legendary: None,
         }
     }
 }
@@ -30,15 +44,164 @@ impl Actions {
     /// Returns a styled [`Line`] displaying the actions available for the combatant.
     ///
     /// [`Line`]: ratatui::text::Line
-    pub fn line(&self) -> ratatui::text::Line {
+    pub fn line(&self) -> ratatui::text::Line<'_> {
         self.actions.iter()
-            .map(|action| action.span())
+            .map(|slot| slot.action.span(slot.used))
+            .chain(self.legendary.map(|legendary| legendary.span()))
             .collect::<Vec<_>>()
             .into()
     }
+
+    /// Returns true if the given action is available to spend.
+    pub fn available(&self, action: Action) -> bool {
+        self.actions.iter().any(|slot| slot.action == action && !slot.used)
+    }
+
+    /// Spends the given action, if the combatant has it available.
+    ///
+    /// Returns true if the action was spent.
+    pub fn spend(&mut self, action: Action) -> bool {
+        match self.actions.iter_mut().find(|slot| slot.action == action && !slot.used) {
+            Some(slot) => {
+                slot.used = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Spends one legendary action, if the combatant has any left this round.
+    ///
+    /// Returns true if a legendary action was spent.
+    pub fn spend_legendary(&mut self) -> bool {
+        match &mut self.legendary {
+            Some(legendary) if legendary.left() > 0 => {
+                legendary.used += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns the actions the combatant has every turn, leaving out extra actions granted for a
+    /// single turn.
+    pub fn kinds(&self) -> Vec<Action> {
+        self.actions.iter()
+            .filter(|slot| !slot.temporary)
+            .map(|slot| slot.action)
+            .collect()
+    }
+
+    /// Replaces the actions the combatant has every turn. Actions the combatant already had stay
+    /// used if they were used, and extra actions granted for this turn are kept.
+    pub fn set_kinds(&mut self, kinds: &[Action]) {
+        let mut old = std::mem::take(&mut self.actions);
+        self.actions = kinds.iter()
+            .map(|&action| match old.iter().position(|slot| slot.action == action && !slot.temporary) {
+                Some(idx) => old.remove(idx),
+                None => ActionSlot::new(action),
+            })
+            .collect();
+        self.actions.extend(old.into_iter().filter(|slot| slot.temporary));
+    }
+
+    /// Grants an extra action that lasts until the start of the combatant's next turn, such as
+    /// from a fighter's Action Surge.
+    pub fn grant(&mut self, action: Action) {
+        self.actions.push(ActionSlot { action, used: false, temporary: true });
+    }
+
+    /// Makes all actions available again, such as at the start of the combatant's turn. Extra
+    /// actions granted for the last turn are lost.
+    pub fn refresh(&mut self) {
+        self.actions.retain(|slot| !slot.temporary);
+        for slot in &mut self.actions {
+            slot.used = false;
+        }
+        if let Some(legendary) = &mut self.legendary {
+            legendary.used = 0;
+        }
+    }
+}
+
+/// The legendary actions of a legendary creature, which it can take at the end of other
+/// creatures' turns. They are regained at the start of the creature's own turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegendaryActions {
+    /// The number of legendary actions the creature can take each round.
+    pub per_round: u32,
+
+    /// The number of legendary actions taken since the creature's last turn.
+    #[serde(default)]
+    pub used: u32,
+}
+
+impl LegendaryActions {
+    /// Returns the number of legendary actions left this round.
+    pub fn left(&self) -> u32 {
+        self.per_round.saturating_sub(self.used)
+    }
+
+    /// Returns a styled [`Span`] displaying the legendary actions left, e.g. "L2". The span is
+    /// dimmed once all of them are used.
+    ///
+    /// [`Span`]: ratatui::text::Span
+    pub fn span(&self) -> ratatui::text::Span<'static> {
+        use ratatui::prelude::*;
+        let span = Span::styled(format!("L{}", self.left()), Style::default().fg(Color::Magenta));
+        if self.left() == 0 {
+            span.fg(Color::DarkGray).add_modifier(Modifier::DIM)
+        } else {
+            span
+        }
+    }
+}
+
+/// An action a combatant has, and whether it has been used since the combatant's last turn.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActionSlot {
+    /// The action.
+    pub action: Action,
+
+    /// Whether the action has been used.
+    #[serde(default)]
+    pub used: bool,
+
+    /// Whether the action was granted for a single turn, and is lost at the start of the
+    /// combatant's next turn.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub temporary: bool,
+}
+
+impl ActionSlot {
+    /// Creates an available action slot.
+    pub fn new(action: Action) -> ActionSlot {
+        ActionSlot { action, used: false, temporary: false }
+    }
+}
+
+/// Parses a list of actions separated by spaces, commas or slashes, e.g. "M A BA R". An action
+/// listed twice is available twice each turn. The limited action granted by Haste is listed as
+/// LA, and stays in the list until it is removed, such as when the spell ends.
+pub fn parse_list(list: &str) -> Result<Vec<Action>, String> {
+    let list = list.to_ascii_lowercase().replace("bonus action", "ba").replace("limited action", "la");
+    list.split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+        .filter(|name| !name.is_empty())
+        .map(|name| Action::parse(name).ok_or_else(|| format!("unknown action \"{}\"", name)))
+        .collect()
+}
+
+/// Formats a list of actions as entered in the form, e.g. "M A BA R".
+pub fn format_list(actions: &[Action]) -> String {
+    actions.iter()
+        .map(|action| action.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 /// An action that a combatant can take.
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
     /// Move up to the combatant's speed.
     Move,
@@ -52,19 +215,53 @@ pub enum Action {
 
     /// Take a reaction (e.g. opportunity attack, shield spell, readied action, etc.).
     Reaction,
+
+    /// Take an action limited to a few options, such as the extra action granted by Haste, which
+    /// can only be used to make one weapon attack, Dash, Disengage, Hide, or Use an Object.
+    Limited,
 }
 
 impl Action {
-    /// Returns a styled [`Span`] displaying the action.
+    /// Returns the name of the action.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Move => "move",
+            Action::Action => "action",
+            Action::BonusAction => "bonus action",
+            Action::Reaction => "reaction",
+            Action::Limited => "limited action",
+        }
+    }
+
+    /// Parses an action from its name or abbreviation, ignoring case.
+    pub fn parse(name: &str) -> Option<Action> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "m" | "move" => Some(Action::Move),
+            "a" | "action" => Some(Action::Action),
+            "ba" | "bonus" => Some(Action::BonusAction),
+            "r" | "reaction" => Some(Action::Reaction),
+            "la" | "limited" => Some(Action::Limited),
+            _ => None,
+        }
+    }
+
+    /// Returns a styled [`Span`] displaying the action. Used actions are dimmed and struck
+    /// through.
     ///
     /// [`Span`]: ratatui::text::Span
-    pub fn span(&self) -> ratatui::text::Span {
+    pub fn span(&self, used: bool) -> ratatui::text::Span<'static> {
         use ratatui::prelude::*;
-        match self {
+        let span = match self {
             Action::Move => Span::styled("M", Style::default().fg(Color::Green)),
             Action::Action => Span::styled("A", Style::default().fg(Color::Yellow)),
             Action::BonusAction => Span::styled("BA", Style::default().fg(Color::Blue)),
             Action::Reaction => Span::styled("R", Style::default().fg(Color::Red)),
+            Action::Limited => Span::styled("LA", Style::default().fg(Color::Cyan)),
+        };
+        if used {
+            span.fg(Color::DarkGray).add_modifier(Modifier::DIM | Modifier::CROSSED_OUT)
+        } else {
+            span
         }
     }
 }
@@ -72,10 +269,11 @@ impl Action {
 impl std::fmt::Display for Action {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Action::Move => write!(f, "{}", "M"),
-            Action::Action => write!(f, "{}", "A"),
-            Action::BonusAction => write!(f, "{}", "BA"),
-            Action::Reaction => write!(f, "{}", "R"),
+            Action::Move => write!(f, "M"),
+            Action::Action => write!(f, "A"),
+            Action::BonusAction => write!(f, "BA"),
+            Action::Reaction => write!(f, "R"),
+            Action::Limited => write!(f, "LA"),
             // Action::Move => write!(f, "{}", "M".green()),
             // Action::Action => write!(f, "{}", "A".fg_rgb::<182, 165, 23>()),
             // Action::BonusAction => write!(f, "{}", "BA".blue()),
@@ -83,3 +281,87 @@ impl std::fmt::Display for Action {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions(kinds: &[Action]) -> Actions {
+        let mut actions = Actions::default();
+        actions.set_kinds(kinds);
+        actions
+    }
+
+    fn used(actions: &Actions) -> Vec<(Action, bool)> {
+        actions.actions.iter().map(|slot| (slot.action, slot.used)).collect()
+    }
+
+    #[test]
+    fn set_kinds_keeps_used_actions() {
+        let mut actions = actions(&[Action::Move, Action::Action, Action::Reaction]);
+        assert!(actions.spend(Action::Action));
+        actions.set_kinds(&[Action::Move, Action::Action, Action::Limited, Action::Reaction]);
+        assert_eq!(used(&actions), [
+            (Action::Move, false),
+            (Action::Action, true),
+            (Action::Limited, false),
+            (Action::Reaction, false),
+        ]);
+        assert!(actions.spend(Action::Limited));
+        assert!(!actions.spend(Action::Limited));
+    }
+
+    #[test]
+    fn set_kinds_keeps_granted_actions() {
+        let mut actions = actions(&[Action::Move, Action::Action]);
+        actions.grant(Action::Action);
+        actions.set_kinds(&[Action::Move, Action::Action, Action::BonusAction]);
+        assert_eq!(actions.kinds(), [Action::Move, Action::Action, Action::BonusAction]);
+        assert_eq!(actions.actions.len(), 4);
+        assert!(actions.actions[3].temporary);
+    }
+
+    #[test]
+    fn granted_actions_last_until_refresh() {
+        let mut actions = actions(&[Action::Move, Action::Action]);
+        actions.grant(Action::Action);
+        assert_eq!(actions.kinds(), [Action::Move, Action::Action]);
+        assert!(actions.spend(Action::Action));
+        assert!(actions.spend(Action::Action));
+        assert!(!actions.available(Action::Action));
+
+        actions.refresh();
+        assert_eq!(used(&actions), [(Action::Move, false), (Action::Action, false)]);
+    }
+
+    #[test]
+    fn refresh_restores_legendary_actions() {
+        let mut actions = Actions { legendary: Some(LegendaryActions { per_round: 3, used: 0 }), ..Default::default() };
+        assert!(actions.spend(Action::Reaction));
+        assert!(actions.spend_legendary());
+        assert!(actions.spend_legendary());
+        assert_eq!(actions.to_string(), "M/A/R/L1");
+
+        actions.refresh();
+        assert!(actions.available(Action::Reaction));
+        assert_eq!(actions.legendary.map(|legendary| legendary.left()), Some(3));
+    }
+
+    #[test]
+    fn legendary_actions_run_out() {
+        let mut actions = Actions { legendary: Some(LegendaryActions { per_round: 2, used: 0 }), ..Default::default() };
+        assert!(actions.spend_legendary());
+        assert!(actions.spend_legendary());
+        assert!(!actions.spend_legendary());
+        assert_eq!(actions.legendary.map(|legendary| legendary.left()), Some(0));
+        assert!(!Actions::default().spend_legendary());
+    }
+
+    #[test]
+    fn limited_action_parses_and_formats() {
+        let kinds = parse_list("M, A / limited action BA").unwrap();
+        assert_eq!(kinds, [Action::Move, Action::Action, Action::Limited, Action::BonusAction]);
+        assert_eq!(format_list(&kinds), "M A LA BA");
+        assert!(parse_list("M X").is_err());
+    }
+}
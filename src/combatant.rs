@@ -1,15 +1,135 @@
-use crate::actions::Actions;
-use ratatui::widgets::{Cell, Row};
-use std::borrow::Cow;
+use crate::{
+    ability::{self, Ability},
+    actions::{Action, ActionSlot, Actions, LegendaryActions},
+    compendium::StatBlock,
+    condition::{AppliedCondition, Condition, Duration},
+    damage::{DamageEntry, DamageModifier, DamageType},
+    dice::{Expression, RollMode},
+    resource::{self, Resource},
+};
+use ratatui::{style::Stylize, text::{Line, Text}, widgets::{Cell, Row}};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap};
+
+/// A unique identifier for a combatant, which stays the same even as the initiative order changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CombatantId(pub u64);
+
+/// Whether a combatant is controlled by a player or by the Dungeon Master.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombatantKind {
+    /// A monster or other creature controlled by the Dungeon Master.
+    #[default]
+    Monster,
+
+    /// A player character.
+    Player,
+
+    /// The lair of a legendary creature, which takes lair actions on initiative count 20.
+    Lair,
+}
+
+/// A spell a combatant is concentrating on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Concentration {
+    /// The name of the spell.
+    pub spell: String,
+
+    /// The combatants affected by the spell.
+    #[serde(default)]
+    pub targets: Vec<CombatantId>,
+}
+
+/// The death saving throws a dying player character has made since dropping to 0 hit points.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeathSaves {
+    /// The number of successes, from 0 to 3. Three successes make the character stable.
+    pub successes: u8,
+
+    /// The number of failures, from 0 to 3. Three failures kill the character.
+    pub failures: u8,
+}
+
+/// What happened to a combatant who took damage that left them at 0 hit points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Downed {
+    /// The player character dropped to 0 hit points and fell unconscious.
+    Unconscious,
+
+    /// The player character took damage while at 0 hit points, failing one death saving throw,
+    /// or two from a critical hit.
+    FailedDeathSave,
+
+    /// The combatant died: a monster dropped to 0 hit points, or a player character failed their
+    /// third death saving throw.
+    Died,
+
+    /// The player character died outright, because the damage left over after dropping to 0 hit
+    /// points equalled their maximum hit points.
+    MassiveDamage,
+}
+
+/// The outcome of a death saving throw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathSaveResult {
+    /// The roll was 10 or higher.
+    Success,
+
+    /// The roll was lower than 10, or a 1, which counts as two failures.
+    Failure,
+
+    /// The third success; the character is stable and stops rolling.
+    Stable,
+
+    /// The third failure; the character dies.
+    Died,
+
+    /// A natural 20; the character regains 1 hit point and wakes up.
+    Revived,
+}
 
 /// A combatant in a combat encounter.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Combatant {
+    /// The identifier of the combatant, assigned by the tracker.
+    #[serde(default)]
+    pub id: CombatantId,
+
     /// The name of the combatant.
     name: String,
 
+    /// Whether the combatant is a player character or a monster.
+    #[serde(default)]
+    pub kind: CombatantKind,
+
     /// The combatant's initiative roll.
+    #[serde(default)]
     pub initiative: i32,
 
+    /// The modifier added to the combatant's initiative rolls, usually their Dexterity modifier.
+    #[serde(default)]
+    pub initiative_modifier: i32,
+
+    /// Whether the combatant rolls initiative with advantage.
+    #[serde(default)]
+    pub initiative_advantage: bool,
+
+    /// The Dungeon Master's chosen order among combatants with the same initiative and initiative
+    /// modifier; higher goes first. This overrides putting player characters first and the
+    /// roll-off.
+    #[serde(default)]
+    pub tie_priority: i32,
+
+    /// The d20 roll-off used to break initiative ties that no other rule can break, recorded so
+    /// the order stays the same for the rest of the encounter.
+    #[serde(default)]
+    pub roll_off: Option<u32>,
+
+    /// The group of identical combatants that share a single initiative roll, identified by the
+    /// first combatant added to the group.
+    #[serde(default)]
+    pub group: Option<CombatantId>,
+
     /// The hit points of the combatant.
     hit_points: i32,
 
@@ -17,43 +137,690 @@ pub struct Combatant {
     max_hit_points: i32,
 
     /// Temporary hit points that the combatant has.
+    #[serde(default)]
     temp_hit_points: i32,
 
+    /// The death saving throws made since the combatant dropped to 0 hit points, if they are a
+    /// player character.
+    #[serde(default)]
+    death_saves: DeathSaves,
+
+    /// Whether the combatant is dead. Dead combatants are skipped in the turn order.
+    #[serde(default)]
+    dead: bool,
+
+    /// The combatant's Armor Class.
+    #[serde(default = "default_armor_class")]
+    pub armor_class: i32,
+
+    /// The combatant's ability scores, in the order of [`Ability::ALL`].
+    #[serde(default = "default_ability_scores")]
+    pub ability_scores: [i32; 6],
+
+    /// The saving throw modifiers the combatant is proficient in. Other saving throws use the
+    /// ability modifier.
+    #[serde(default)]
+    pub saving_throws: BTreeMap<Ability, i32>,
+
+    /// The damage types the combatant takes half damage from.
+    #[serde(default)]
+    pub resistances: Vec<DamageEntry>,
+
+    /// The damage types the combatant takes double damage from.
+    #[serde(default)]
+    pub vulnerabilities: Vec<DamageEntry>,
+
+    /// The damage types the combatant takes no damage from.
+    #[serde(default)]
+    pub immunities: Vec<DamageEntry>,
+
     /// The actions available for the combatant.
+    #[serde(default)]
     actions: Actions,
+
+    /// The conditions affecting the combatant.
+    #[serde(default)]
+    conditions: Vec<AppliedCondition>,
+
+    /// The spell the combatant is concentrating on, if any.
+    #[serde(default)]
+    pub concentration: Option<Concentration>,
+
+    /// The combatant's limited-use resources, such as spell slots and ki points.
+    #[serde(default)]
+    pub resources: Vec<Resource>,
+
+    /// The stat block of the monster the combatant was created from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat_block: Option<Box<StatBlock>>,
+}
+
+/// The Armor Class of a combatant without armor or a Dexterity bonus.
+fn default_armor_class() -> i32 {
+    10
+}
+
+/// The ability scores of an average combatant.
+fn default_ability_scores() -> [i32; 6] {
+    [10; 6]
 }
 
 impl Combatant {
     /// Creates a new combatant with the given name and hit points.
     pub fn new(name: String, hit_points: i32, max_hit_points: i32) -> Combatant {
         Combatant {
+            id: CombatantId::default(),
             name,
+            kind: CombatantKind::default(),
             initiative: 0,
+            initiative_modifier: 0,
+            initiative_advantage: false,
+            tie_priority: 0,
+            roll_off: None,
+            group: None,
             hit_points,
             max_hit_points,
             temp_hit_points: 0,
+            death_saves: DeathSaves::default(),
+            dead: false,
+            armor_class: default_armor_class(),
+            ability_scores: default_ability_scores(),
+            saving_throws: BTreeMap::new(),
+            resistances: Vec::new(),
+            vulnerabilities: Vec::new(),
+            immunities: Vec::new(),
             actions: Actions::default(),
+            conditions: Vec::new(),
+            concentration: None,
+            resources: Vec::new(),
+            stat_block: None,
         }
     }
 
+    /// Creates the pseudo-combatant for lair actions, which has a single action each round on
+    /// initiative count 20.
+    pub fn lair() -> Combatant {
+        let mut lair = Combatant::new("Lair".to_string(), 0, 0);
+        lair.kind = CombatantKind::Lair;
+        lair.initiative = 20;
+        lair.actions = Actions { actions: vec![ActionSlot::new(Action::Action)], legendary: None };
+        lair
+    }
+
     /// Returns the combatant's name.
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Renames the combatant.
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     /// Returns the combatant's initiative.
     pub fn initiative(&self) -> i32 {
         self.initiative
     }
 
+    /// Sets the combatant's initiative, forgetting how any previous ties were broken.
+    pub fn set_initiative(&mut self, initiative: i32) {
+        self.initiative = initiative;
+        self.tie_priority = 0;
+        self.roll_off = None;
+    }
+
+    /// Compares the combatants by initiative order, returning [`Ordering::Less`] if this combatant
+    /// goes first.
+    ///
+    /// The lair loses every tie. Other ties in initiative are broken by, in order: the higher
+    /// initiative modifier, the Dungeon Master's chosen order, player characters before monsters,
+    /// and finally the recorded roll-off.
+    pub fn cmp_initiative(&self, other: &Combatant) -> Ordering {
+        self.cmp_before_roll_off(other)
+            .then_with(|| other.roll_off.cmp(&self.roll_off))
+            .then_with(|| self.id.cmp(&other.id))
+    }
+
+    /// Returns true if the Dungeon Master can choose the order of the combatants: they have the
+    /// same initiative and initiative modifier, and neither is the lair.
+    pub fn can_reorder_with(&self, other: &Combatant) -> bool {
+        self.initiative == other.initiative
+            && self.initiative_modifier == other.initiative_modifier
+            && self.kind != CombatantKind::Lair
+            && other.kind != CombatantKind::Lair
+    }
+
+    /// Returns true if the combatants are tied in initiative until the roll-off.
+    pub fn ties_with(&self, other: &Combatant) -> bool {
+        self.cmp_before_roll_off(other) == Ordering::Equal
+    }
+
+    /// Compares the combatants by initiative order, using every rule except the roll-off.
+    fn cmp_before_roll_off(&self, other: &Combatant) -> Ordering {
+        let players_first = |c: &Combatant| c.kind != CombatantKind::Player;
+        let lair_last = |c: &Combatant| c.kind == CombatantKind::Lair;
+        other.initiative().cmp(&self.initiative())
+            .then_with(|| lair_last(self).cmp(&lair_last(other)))
+            .then_with(|| other.initiative_modifier.cmp(&self.initiative_modifier))
+            .then_with(|| other.tie_priority.cmp(&self.tie_priority))
+            .then_with(|| players_first(self).cmp(&players_first(other)))
+    }
+
+    /// Returns the dice expression for the combatant's initiative roll: a d20 (two, keeping the
+    /// highest, with advantage) plus their initiative modifier.
+    pub fn initiative_expression(&self) -> Expression {
+        let mode = if self.initiative_advantage { RollMode::Advantage } else { RollMode::Normal };
+        Expression::d20(self.initiative_modifier, mode)
+    }
+
+    /// Returns the combatant's score in the given ability.
+    pub fn score(&self, ability: Ability) -> i32 {
+        self.ability_scores[ability as usize]
+    }
+
+    /// Returns the combatant's saving throw modifier for the given ability.
+    pub fn save_modifier(&self, ability: Ability) -> i32 {
+        self.saving_throws.get(&ability)
+            .copied()
+            .unwrap_or_else(|| ability::modifier(self.score(ability)))
+    }
+
+    /// Returns how the combatant's conditions affect their saving throws for the given ability:
+    /// [`None`] if they fail automatically, or whether they roll with advantage or disadvantage.
+    pub fn save_mode(&self, ability: Ability) -> Option<RollMode> {
+        let mut mode = RollMode::Normal;
+        for applied in &self.conditions {
+            match applied.condition {
+                Condition::Paralyzed | Condition::Petrified | Condition::Stunned | Condition::Unconscious
+                    if matches!(ability, Ability::Strength | Ability::Dexterity) => return None,
+                Condition::Restrained if ability == Ability::Dexterity => {
+                    mode = mode.combine(RollMode::Disadvantage);
+                },
+                Condition::Exhaustion(level) if level >= 3 => {
+                    mode = mode.combine(RollMode::Disadvantage);
+                },
+                _ => (),
+            }
+        }
+        Some(mode)
+    }
+
+    /// Returns how the combatant's resistances, vulnerabilities and immunities affect damage of
+    /// the given type. Resistance and vulnerability to the same damage cancel out.
+    pub fn damage_modifier(&self, kind: DamageType, magical: bool) -> DamageModifier {
+        let applies = |entries: &[DamageEntry]| entries.iter().any(|entry| entry.applies(kind, magical));
+        if applies(&self.immunities) {
+            return DamageModifier::Immune;
+        }
+        match (applies(&self.resistances), applies(&self.vulnerabilities)) {
+            (true, false) => DamageModifier::Resistant,
+            (false, true) => DamageModifier::Vulnerable,
+            _ => DamageModifier::Normal,
+        }
+    }
+
+    /// Returns the combatant's current hit points.
+    pub fn hit_points(&self) -> i32 {
+        self.hit_points
+    }
+
+    /// Returns the combatant's maximum hit points.
+    pub fn max_hit_points(&self) -> i32 {
+        self.max_hit_points
+    }
+
+    /// Returns the combatant's temporary hit points.
+    pub fn temp_hit_points(&self) -> i32 {
+        self.temp_hit_points
+    }
+
+    /// Sets the combatant's hit points, maximum hit points and temporary hit points directly,
+    /// such as when correcting a mistake. A combatant given hit points is no longer dead or dying,
+    /// and wakes up if they were knocked out.
+    pub fn set_hit_points(&mut self, hit_points: i32, max_hit_points: i32, temp_hit_points: i32) {
+        let was_down = self.dead || self.hit_points == 0;
+        self.hit_points = hit_points;
+        self.max_hit_points = max_hit_points;
+        self.temp_hit_points = temp_hit_points;
+        if hit_points > 0 && was_down {
+            self.dead = false;
+            self.death_saves = DeathSaves::default();
+            self.remove_condition(&Condition::Unconscious);
+        }
+    }
+
+    /// Returns the death saving throws the combatant has made.
+    pub fn death_saves(&self) -> DeathSaves {
+        self.death_saves
+    }
+
+    /// Returns true if the combatant is dead.
+    pub fn is_dead(&self) -> bool {
+        self.dead
+    }
+
+    /// Returns true if the combatant is a player character at 0 hit points who is neither dead nor
+    /// stable, and so must make death saving throws.
+    pub fn is_dying(&self) -> bool {
+        self.kind == CombatantKind::Player && self.hit_points == 0 && !self.dead && self.death_saves.successes < 3
+    }
+
+    /// Returns true if the combatant is a player character at 0 hit points who has been
+    /// stabilized.
+    pub fn is_stable(&self) -> bool {
+        self.kind == CombatantKind::Player && self.hit_points == 0 && !self.dead && self.death_saves.successes >= 3
+    }
+
+    /// Deals damage to the combatant. Temporary hit points are lost first, and any leftover damage
+    /// is dealt to the combatant's hit points, which cannot drop below 0.
+    ///
+    /// A monster dropping to 0 hit points dies. A player character falls unconscious instead, and
+    /// fails a death saving throw (two from a critical hit) whenever they are damaged at 0 hit
+    /// points. Returns what happened if the combatant was left at 0 hit points.
+    pub fn take_damage(&mut self, amount: i32, critical: bool) -> Option<Downed> {
+        if self.dead || self.kind == CombatantKind::Lair {
+            return None;
+        }
+        let absorbed = amount.min(self.temp_hit_points);
+        self.temp_hit_points -= absorbed;
+        let amount = amount - absorbed;
+        if amount <= 0 {
+            return None;
+        }
+
+        let was_down = self.hit_points == 0;
+        let excess = amount - self.hit_points;
+        self.hit_points = (self.hit_points - amount).max(0);
+        if self.hit_points > 0 {
+            return None;
+        }
+        if self.kind == CombatantKind::Monster {
+            self.dead = true;
+            return Some(Downed::Died);
+        }
+        if excess >= self.max_hit_points {
+            self.dead = true;
+            return Some(Downed::MassiveDamage);
+        }
+        if !was_down {
+            self.death_saves = DeathSaves::default();
+            self.add_condition(AppliedCondition { condition: Condition::Unconscious, duration: None, concentration: None });
+            return Some(Downed::Unconscious);
+        }
+
+        // a stable character who takes damage starts making death saving throws again
+        if self.death_saves.successes >= 3 {
+            self.death_saves.successes = 0;
+        }
+        self.death_saves.failures += if critical { 2 } else { 1 };
+        if self.death_saves.failures >= 3 {
+            self.death_saves.failures = 3;
+            self.dead = true;
+            return Some(Downed::Died);
+        }
+        Some(Downed::FailedDeathSave)
+    }
+
+    /// Records a death saving throw with the given natural d20 roll. A 1 counts as two failures,
+    /// and a 20 brings the character back to 1 hit point.
+    pub fn death_save(&mut self, natural: u32) -> DeathSaveResult {
+        match natural {
+            20 => {
+                self.heal(1);
+                DeathSaveResult::Revived
+            },
+            10.. => {
+                self.death_saves.successes += 1;
+                if self.death_saves.successes >= 3 {
+                    DeathSaveResult::Stable
+                } else {
+                    DeathSaveResult::Success
+                }
+            },
+            _ => {
+                self.death_saves.failures = (self.death_saves.failures + if natural == 1 { 2 } else { 1 }).min(3);
+                if self.death_saves.failures >= 3 {
+                    self.dead = true;
+                    DeathSaveResult::Died
+                } else {
+                    DeathSaveResult::Failure
+                }
+            },
+        }
+    }
+
+    /// Heals the combatant, up to their maximum hit points. A character healed from 0 hit points
+    /// regains consciousness, while the dead cannot be healed.
+    pub fn heal(&mut self, amount: i32) {
+        if self.dead || amount <= 0 {
+            return;
+        }
+        if self.hit_points == 0 {
+            self.death_saves = DeathSaves::default();
+            self.remove_condition(&Condition::Unconscious);
+        }
+        self.hit_points = self.hit_points.saturating_add(amount).min(self.max_hit_points);
+    }
+
+    /// Grants temporary hit points to the combatant. Temporary hit points don't stack, so the
+    /// combatant keeps whichever is higher: the new temporary hit points or the ones they already
+    /// have.
+    pub fn grant_temp_hit_points(&mut self, amount: i32) {
+        self.temp_hit_points = self.temp_hit_points.max(amount);
+    }
+
+    /// Returns true if the combatant has the given action available to spend.
+    pub fn has_action(&self, action: Action) -> bool {
+        self.actions.available(action)
+    }
+
+    /// Spends one of the combatant's actions, returning true if it was available.
+    pub fn spend_action(&mut self, action: Action) -> bool {
+        self.actions.spend(action)
+    }
+
+    /// Returns the actions the combatant has every turn.
+    pub fn action_kinds(&self) -> Vec<Action> {
+        self.actions.kinds()
+    }
+
+    /// Sets the actions the combatant has every turn, such as adding a bonus action.
+    pub fn set_action_kinds(&mut self, kinds: &[Action]) {
+        self.actions.set_kinds(kinds);
+    }
+
+    /// Grants the combatant an extra action until the start of their next turn.
+    pub fn grant_action(&mut self, action: Action) {
+        self.actions.grant(action);
+    }
+
+    /// Returns the combatant's legendary actions, if they are a legendary creature.
+    pub fn legendary_actions(&self) -> Option<LegendaryActions> {
+        self.actions.legendary
+    }
+
+    /// Sets the number of legendary actions the combatant can take each round, keeping the number
+    /// already taken this round. A combatant with none is not a legendary creature.
+    pub fn set_legendary_actions(&mut self, per_round: u32) {
+        let used = self.actions.legendary.map_or(0, |legendary| legendary.used.min(per_round));
+        self.actions.legendary = (per_round > 0).then_some(LegendaryActions { per_round, used });
+    }
+
+    /// Spends one of the combatant's legendary actions, returning true if they had one left.
+    pub fn spend_legendary_action(&mut self) -> bool {
+        self.actions.spend_legendary()
+    }
+
+    /// Makes all of the combatant's actions available again.
+    pub fn refresh_actions(&mut self) {
+        self.actions.refresh();
+    }
+
+    /// Returns the conditions affecting the combatant.
+    pub fn conditions(&self) -> &[AppliedCondition] {
+        &self.conditions
+    }
+
+    /// Returns the combatant's instance of the given kind of condition, if any.
+    pub fn condition(&self, condition: &Condition) -> Option<&AppliedCondition> {
+        self.conditions.iter().find(|applied| applied.condition.same_kind(condition))
+    }
+
+    /// Applies a condition to the combatant, replacing any existing condition of the same kind. A
+    /// combatant who reaches the highest level of exhaustion dies.
+    pub fn add_condition(&mut self, applied: AppliedCondition) {
+        self.remove_condition(&applied.condition);
+        if matches!(applied.condition, Condition::Exhaustion(level) if level >= Condition::MAX_EXHAUSTION) {
+            self.dead = true;
+        }
+        self.conditions.push(applied);
+    }
+
+    /// Removes the given kind of condition from the combatant.
+    pub fn remove_condition(&mut self, condition: &Condition) {
+        self.conditions.retain(|applied| !applied.condition.same_kind(condition));
+    }
+
+    /// Removes the conditions that last until the end of the given combatant's turn or that are
+    /// maintained by their concentration, such as when that combatant leaves the encounter.
+    pub fn remove_conditions_from(&mut self, source: CombatantId) {
+        self.conditions.retain(|applied| !matches!(
+            applied.duration,
+            Some(Duration::EndOfSourceTurn { source: s, .. }) if s == source
+        ) && applied.concentration != Some(source));
+    }
+
+    /// Removes the conditions maintained by the given combatant's concentration, which has ended.
+    pub fn remove_concentration_conditions(&mut self, caster: CombatantId) {
+        self.conditions.retain(|applied| applied.concentration != Some(caster));
+    }
+
+    /// Counts down the durations of the combatant's conditions at the end of the given
+    /// combatant's turn, removing any that expire.
+    pub fn end_turn(&mut self, ended: CombatantId) {
+        let id = self.id;
+        self.conditions.retain_mut(|applied| !applied.end_turn(id, ended));
+    }
+
+    /// Returns the text of the combatant's name cell, with a summary of their resources below the
+    /// name if they have any.
+    fn name_text(&self) -> Text<'_> {
+        if self.resources.is_empty() {
+            return Text::from(self.name.as_str());
+        }
+        Text::from(vec![
+            Line::from(self.name.as_str()),
+            Line::from(resource::summary(&self.resources)).dark_gray(),
+        ])
+    }
+
+    /// Returns the text of the combatant's hit points cell, which shows whether they are dead,
+    /// stable, or their death saving throws while dying.
+    fn hit_points_text(&self) -> Text<'_> {
+        if self.kind == CombatantKind::Lair {
+            return Text::default();
+        }
+        let hit_points = Line::from(format!("{} / {}", self.hit_points, self.max_hit_points));
+        if self.dead {
+            Text::from(vec![hit_points, Line::from("dead")])
+        } else if self.is_stable() {
+            Text::from(vec![hit_points, Line::from("stable")])
+        } else if self.is_dying() {
+            let saves = format!("✓{} ✗{}", self.death_saves.successes, self.death_saves.failures);
+            Text::from(vec![hit_points, Line::from(saves)])
+        } else {
+            Text::from(hit_points)
+        }
+    }
+
     /// Builds the combatant's table row.
-    pub fn row(&self) -> Row {
+    pub fn row(&self) -> Row<'_> {
+        // the lair has no statistics of its own
+        let lair = self.kind == CombatantKind::Lair;
         Row::new([
             Cell::from(self.initiative.to_string()),
-            Cell::from(&*self.name),
+            Cell::from(self.name_text()),
+            Cell::from(if lair { String::new() } else { self.armor_class.to_string() }),
             Cell::from(self.actions.line()),
-            Cell::from(format!("{} / {}", self.hit_points, self.max_hit_points)),
-            Cell::from(self.temp_hit_points.to_string()),
+            Cell::from(self.hit_points_text()),
+            Cell::from(if lair { String::new() } else { self.temp_hit_points.to_string() }),
+            Cell::from(
+                self.concentration.iter()
+                    .map(|concentration| format!("Concentrating ({})", concentration.spell))
+                    .chain(self.conditions.iter().map(|applied| applied.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         ])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::damage::DamageInstance;
+
+    /// Returns a player character with the given hit points.
+    fn player(hit_points: i32) -> Combatant {
+        let mut combatant = Combatant::new("Ayla".to_string(), hit_points, hit_points);
+        combatant.kind = CombatantKind::Player;
+        combatant
+    }
+
+    /// Returns a player character who has just dropped to 0 hit points.
+    fn downed() -> Combatant {
+        let mut combatant = player(10);
+        assert_eq!(combatant.take_damage(10, false), Some(Downed::Unconscious));
+        combatant
+    }
+
+    #[test]
+    fn temp_hit_points_absorb_damage() {
+        let mut combatant = player(10);
+        combatant.grant_temp_hit_points(5);
+        assert_eq!(combatant.take_damage(3, false), None);
+        assert_eq!((combatant.hit_points(), combatant.temp_hit_points()), (10, 2));
+        assert_eq!(combatant.take_damage(6, false), None);
+        assert_eq!((combatant.hit_points(), combatant.temp_hit_points()), (6, 0));
+    }
+
+    #[test]
+    fn temp_hit_points_do_not_stack() {
+        let mut combatant = player(10);
+        combatant.grant_temp_hit_points(5);
+        combatant.grant_temp_hit_points(3);
+        assert_eq!(combatant.temp_hit_points(), 5);
+        combatant.grant_temp_hit_points(8);
+        assert_eq!(combatant.temp_hit_points(), 8);
+    }
+
+    #[test]
+    fn healing_does_not_restore_temp_hit_points() {
+        let mut combatant = player(10);
+        combatant.grant_temp_hit_points(4);
+        combatant.take_damage(6, false);
+        assert_eq!((combatant.hit_points(), combatant.temp_hit_points()), (8, 0));
+        combatant.heal(20);
+        assert_eq!((combatant.hit_points(), combatant.temp_hit_points()), (10, 0));
+    }
+
+    #[test]
+    fn half_damage_on_a_successful_save() {
+        let fireball = DamageInstance { amount: 27, kind: Some(DamageType::Fire), magical: true };
+        let mut combatant = player(20);
+        combatant.take_damage(fireball.halved().amount, false);
+        assert_eq!(combatant.hit_points(), 7);
+
+        // halving rounds down, and happens before resistance halves it again
+        let mut resistant = player(20);
+        resistant.resistances = DamageEntry::parse_list("fire").unwrap();
+        let modifier = resistant.damage_modifier(DamageType::Fire, true);
+        resistant.take_damage(modifier.apply(fireball.halved().amount), false);
+        assert_eq!(resistant.hit_points(), 14);
+    }
+
+    #[test]
+    fn monster_dies_at_zero() {
+        let mut combatant = Combatant::new("Goblin".to_string(), 7, 7);
+        assert_eq!(combatant.take_damage(9, false), Some(Downed::Died));
+        assert!(combatant.is_dead());
+        assert_eq!(combatant.take_damage(5, false), None);
+    }
+
+    #[test]
+    fn player_falls_unconscious() {
+        let combatant = downed();
+        assert_eq!(combatant.hit_points(), 0);
+        assert!(combatant.is_dying());
+        assert!(combatant.condition(&Condition::Unconscious).is_some());
+    }
+
+    #[test]
+    fn massive_damage() {
+        let mut combatant = player(10);
+        assert_eq!(combatant.take_damage(20, false), Some(Downed::MassiveDamage));
+        assert!(combatant.is_dead());
+
+        // the leftover damage must reach the maximum hit points
+        let mut combatant = player(10);
+        assert_eq!(combatant.take_damage(19, false), Some(Downed::Unconscious));
+    }
+
+    #[test]
+    fn damage_at_zero_fails_death_saves() {
+        let mut combatant = downed();
+        assert_eq!(combatant.take_damage(1, false), Some(Downed::FailedDeathSave));
+        assert_eq!(combatant.death_saves().failures, 1);
+        assert_eq!(combatant.take_damage(1, true), Some(Downed::Died));
+        assert_eq!(combatant.death_saves().failures, 3);
+        assert!(combatant.is_dead());
+    }
+
+    #[test]
+    fn critical_at_zero_counts_twice() {
+        let mut combatant = downed();
+        assert_eq!(combatant.take_damage(1, true), Some(Downed::FailedDeathSave));
+        assert_eq!(combatant.death_saves().failures, 2);
+    }
+
+    #[test]
+    fn natural_one_counts_twice() {
+        let mut combatant = downed();
+        assert_eq!(combatant.death_save(1), DeathSaveResult::Failure);
+        assert_eq!(combatant.death_saves().failures, 2);
+        assert_eq!(combatant.death_save(9), DeathSaveResult::Died);
+        assert!(combatant.is_dead());
+    }
+
+    #[test]
+    fn natural_twenty_revives() {
+        let mut combatant = downed();
+        combatant.death_save(5);
+        assert_eq!(combatant.death_save(20), DeathSaveResult::Revived);
+        assert_eq!(combatant.hit_points(), 1);
+        assert_eq!(combatant.death_saves(), DeathSaves::default());
+        assert!(combatant.condition(&Condition::Unconscious).is_none());
+    }
+
+    #[test]
+    fn three_successes_stabilize() {
+        let mut combatant = downed();
+        assert_eq!(combatant.death_save(10), DeathSaveResult::Success);
+        assert_eq!(combatant.death_save(15), DeathSaveResult::Success);
+        assert_eq!(combatant.death_save(19), DeathSaveResult::Stable);
+        assert!(combatant.is_stable());
+        assert!(!combatant.is_dying());
+    }
+
+    #[test]
+    fn stable_character_hit_again() {
+        let mut combatant = downed();
+        for _ in 0..3 {
+            combatant.death_save(12);
+        }
+        assert_eq!(combatant.take_damage(2, false), Some(Downed::FailedDeathSave));
+        assert_eq!(combatant.death_saves(), DeathSaves { successes: 0, failures: 1 });
+        assert!(combatant.is_dying());
+    }
+
+    #[test]
+    fn setting_hit_points_wakes_the_downed() {
+        let mut combatant = downed();
+        combatant.take_damage(1, false);
+        combatant.set_hit_points(5, 10, 0);
+        assert!(!combatant.is_dying());
+        assert_eq!(combatant.death_saves(), DeathSaves::default());
+        assert!(combatant.condition(&Condition::Unconscious).is_none());
+    }
+
+    #[test]
+    fn highest_exhaustion_kills() {
+        let mut combatant = player(10);
+        let exhaustion = |level| AppliedCondition { condition: Condition::Exhaustion(level), duration: None, concentration: None };
+        combatant.add_condition(exhaustion(5));
+        assert!(!combatant.is_dead());
+        combatant.add_condition(exhaustion(Condition::MAX_EXHAUSTION));
+        assert!(combatant.is_dead());
+        assert_eq!(combatant.conditions().len(), 1);
+    }
+}
@@ -0,0 +1,506 @@
+//! A library of monster stat blocks to pick combatants from.
+//!
+//! A small selection of monsters from the System Reference Document 5.1 is bundled with the
+//! tracker. More monsters can be added in `$XDG_CONFIG_HOME/initiative-tracker/monsters.json`,
+//! falling back to `~/.config/initiative-tracker/monsters.json`. Monsters in that file replace
+//! bundled monsters with the same name.
+//!
+//! The file holds a JSON array of stat blocks. Only `name`, `armor_class` and `hit_points` are
+//! required:
+//!
+//! ```json
+//! [
+//!   {
+//!     "name": "Goblin",
+//!     "size": "Small",
+//!     "type": "humanoid (goblinoid)",
+//!     "alignment": "neutral evil",
+//!     "armor_class": 15,
+//!     "armor_desc": "leather armor, shield",
+//!     "hit_points": 7,
+//!     "hit_dice": "2d6",
+//!     "speed": "30 ft.",
+//!     "strength": 8,
+//!     "dexterity": 14,
+//!     "constitution": 10,
+//!     "intelligence": 10,
+//!     "wisdom": 8,
+//!     "charisma": 8,
+//!     "saving_throws": { "dexterity": 4 },
+//!     "skills": "Stealth +6",
+//!     "damage_vulnerabilities": "",
+//!     "damage_resistances": "",
+//!     "damage_immunities": "",
+//!     "condition_immunities": "",
+//!     "senses": "darkvision 60 ft., passive Perception 9",
+//!     "languages": "Common, Goblin",
+//!     "challenge_rating": "1/4",
+//!     "traits": [{ "name": "Nimble Escape", "desc": "..." }],
+//!     "actions": [{ "name": "Scimitar", "desc": "..." }],
+//!     "reactions": [],
+//!     "legendary_desc": "",
+//!     "legendary_actions": []
+//!   }
+//! ]
+//! ```
+
+use crate::{
+    ability::{self, Ability},
+    damage::DamageType,
+    resource::{self, Resource, ResourceKind},
+};
+use ratatui::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+
+/// The monsters bundled with the tracker.
+const BUNDLED: &str = include_str!("../data/monsters.json");
+
+/// A named feature of a stat block, such as a trait or an action.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Feature {
+    /// The name of the feature, e.g. "Scimitar" or "Acid Breath (Recharge 5-6)".
+    pub name: String,
+
+    /// The full rules text of the feature.
+    #[serde(default)]
+    pub desc: String,
+}
+
+/// An attack parsed from the rules text of an action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attack {
+    /// The name of the action, e.g. "Scimitar".
+    pub name: String,
+
+    /// The bonus to the attack roll.
+    pub bonus: i32,
+
+    /// The damage dealt on a hit, e.g. "1d6+2 slashing", or "2d10+6 piercing plus 1d8 acid" for
+    /// damage of several types.
+    pub damage: String,
+}
+
+impl Feature {
+    /// Parses the feature as an attack, if its rules text reads like one: "Melee Weapon Attack: +4
+    /// to hit, ... Hit: 5 (1d6 + 2) slashing damage plus 3 (1d6) fire damage."
+    pub fn attack(&self) -> Option<Attack> {
+        let (_, after_attack) = self.desc.split_once("Attack:")?;
+        let bonus = after_attack.split_whitespace()
+            .next()?
+            .trim_start_matches('+')
+            .parse()
+            .ok()?;
+
+        // the damage is in the first sentence after "Hit:", with any extra damage joined by "plus",
+        // and each group of dice is followed by its damage type
+        let (_, hit) = after_attack.split_once("Hit:")?;
+        let hit = hit.split(". ").next()?;
+        let damage = hit.split(" plus ")
+            .map_while(|part| {
+                let (_, rest) = part.split_once('(')?;
+                let (dice, rest) = rest.split_once(')')?;
+                let dice = dice.contains('d').then(|| dice.replace(' ', ""))?;
+                Some(match rest.split_whitespace().next().and_then(DamageType::parse) {
+                    Some(kind) => format!("{} {}", dice, kind),
+                    None => dice,
+                })
+            })
+            .collect::<Vec<_>>();
+        if damage.is_empty() {
+            return None;
+        }
+        Some(Attack { name: self.name.clone(), bonus, damage: damage.join(" plus ") })
+    }
+}
+
+/// The game statistics of a monster.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatBlock {
+    /// The name of the monster.
+    pub name: String,
+
+    /// The size of the monster, e.g. "Medium".
+    #[serde(default)]
+    pub size: String,
+
+    /// The type of the monster, e.g. "humanoid (goblinoid)".
+    #[serde(default, rename = "type")]
+    pub kind: String,
+
+    /// The alignment of the monster.
+    #[serde(default)]
+    pub alignment: String,
+
+    /// The Armor Class of the monster.
+    pub armor_class: i32,
+
+    /// What the Armor Class comes from, e.g. "natural armor".
+    #[serde(default)]
+    pub armor_desc: String,
+
+    /// The average hit points of the monster.
+    pub hit_points: i32,
+
+    /// The dice rolled for the monster's hit points, e.g. "2d8+2".
+    #[serde(default)]
+    pub hit_dice: String,
+
+    /// The speeds of the monster, e.g. "40 ft., fly 80 ft.".
+    #[serde(default)]
+    pub speed: String,
+
+    #[serde(default = "default_score")]
+    pub strength: i32,
+
+    #[serde(default = "default_score")]
+    pub dexterity: i32,
+
+    #[serde(default = "default_score")]
+    pub constitution: i32,
+
+    #[serde(default = "default_score")]
+    pub intelligence: i32,
+
+    #[serde(default = "default_score")]
+    pub wisdom: i32,
+
+    #[serde(default = "default_score")]
+    pub charisma: i32,
+
+    /// The saving throw modifiers the monster is proficient in. Other saving throws use the
+    /// ability modifier.
+    #[serde(default)]
+    pub saving_throws: BTreeMap<Ability, i32>,
+
+    #[serde(default)]
+    pub skills: String,
+
+    #[serde(default)]
+    pub damage_vulnerabilities: String,
+
+    #[serde(default)]
+    pub damage_resistances: String,
+
+    #[serde(default)]
+    pub damage_immunities: String,
+
+    #[serde(default)]
+    pub condition_immunities: String,
+
+    #[serde(default)]
+    pub senses: String,
+
+    #[serde(default)]
+    pub languages: String,
+
+    /// The challenge rating, e.g. "1/4".
+    #[serde(default)]
+    pub challenge_rating: String,
+
+    /// Special traits, which are always in effect.
+    #[serde(default)]
+    pub traits: Vec<Feature>,
+
+    #[serde(default)]
+    pub actions: Vec<Feature>,
+
+    #[serde(default)]
+    pub reactions: Vec<Feature>,
+
+    /// The introduction to the legendary actions, e.g. "The dragon can take 3 legendary actions,
+    /// choosing from the options below."
+    #[serde(default)]
+    pub legendary_desc: String,
+
+    #[serde(default)]
+    pub legendary_actions: Vec<Feature>,
+}
+
+/// The ability score used when a stat block leaves one out.
+fn default_score() -> i32 {
+    10
+}
+
+impl StatBlock {
+    /// Returns the monster's initiative modifier, which is its Dexterity modifier.
+    pub fn initiative_modifier(&self) -> i32 {
+        ability::modifier(self.dexterity)
+    }
+
+    /// Returns the attacks among the monster's actions.
+    pub fn attacks(&self) -> Vec<Attack> {
+        self.actions.iter().filter_map(Feature::attack).collect()
+    }
+
+    /// Returns the number of legendary actions the monster can take each round, as given by its
+    /// legendary actions introduction, or 3 if it doesn't say. Monsters without legendary actions
+    /// have none.
+    pub fn legendary_actions_per_round(&self) -> u32 {
+        if self.legendary_actions.is_empty() {
+            return 0;
+        }
+        let words = self.legendary_desc.split_whitespace().collect::<Vec<_>>();
+        words.windows(2)
+            .find(|pair| pair[1].starts_with("legendary"))
+            .and_then(|pair| pair[0].parse().ok())
+            .unwrap_or(3)
+    }
+
+    /// Returns the limited-use resources described by the monster's features: abilities usable a
+    /// number of times per day, such as "Legendary Resistance (3/Day)", abilities that recharge,
+    /// such as "Fire Breath (Recharge 5-6)", and the spell slots of a Spellcasting trait, written
+    /// like "1st level (4 slots)".
+    pub fn resources(&self) -> Vec<Resource> {
+        let features = || self.traits.iter()
+            .chain(&self.actions)
+            .chain(&self.reactions)
+            .chain(&self.legendary_actions);
+        let mut resources = Vec::new();
+        for feature in features().filter(|feature| feature.name.starts_with("Spellcasting")) {
+            // e.g. "... 1st level (4 slots): ...", with the level just before each split
+            let parts = feature.desc.split(" level (").collect::<Vec<_>>();
+            for pair in parts.windows(2) {
+                let level = pair[0].split_whitespace()
+                    .last()
+                    .and_then(|ordinal| ordinal.trim_end_matches(char::is_alphabetic).parse::<u8>().ok());
+                let mut words = pair[1].split_whitespace();
+                let slots = words.next().and_then(|slots| slots.parse().ok());
+                let is_slots = words.next().is_some_and(|word| word.starts_with("slot"));
+                if let (Some(level), Some(slots), true) = (level, slots, is_slots) {
+                    resources.push(Resource::new(ResourceKind::SpellSlot(level), "", slots));
+                }
+            }
+        }
+        for feature in features() {
+            let Some((name, uses)) = feature.name.split_once(" (") else {
+                continue;
+            };
+            let uses = uses.to_ascii_lowercase();
+            if let Some(min) = uses.strip_prefix("recharge ").and_then(|range| resource::parse_recharge(range.trim_end_matches(')'))) {
+                resources.push(Resource::new(ResourceKind::Recharge(min), name, 1));
+                continue;
+            }
+            let Some(uses) = uses.strip_suffix("/day)").and_then(|uses| uses.parse().ok()) else {
+                continue;
+            };
+            let kind = if name == "Legendary Resistance" { ResourceKind::LegendaryResistance } else { ResourceKind::PerDay };
+            resources.push(Resource::new(kind, if kind == ResourceKind::PerDay { name } else { "" }, uses));
+        }
+        resources
+    }
+
+    /// Returns the monster's score in the given ability.
+    pub fn score(&self, ability: Ability) -> i32 {
+        match ability {
+            Ability::Strength => self.strength,
+            Ability::Dexterity => self.dexterity,
+            Ability::Constitution => self.constitution,
+            Ability::Intelligence => self.intelligence,
+            Ability::Wisdom => self.wisdom,
+            Ability::Charisma => self.charisma,
+        }
+    }
+
+    /// Renders the stat block as text, laid out like a stat block in the Monster Manual.
+    pub fn text(&self) -> Text<'_> {
+        let property = |name: &'static str, value: String| {
+            Line::from(vec![Span::from(format!("{} ", name)).bold(), Span::from(value)])
+        };
+        let mut lines = vec![
+            Line::from(self.name.as_str()).bold(),
+            Line::from(format!("{} {}, {}", self.size, self.kind, self.alignment)).italic(),
+            Line::default(),
+            property("Armor Class", match self.armor_desc.as_str() {
+                "" => self.armor_class.to_string(),
+                desc => format!("{} ({})", self.armor_class, desc),
+            }),
+            property("Hit Points", match self.hit_dice.as_str() {
+                "" => self.hit_points.to_string(),
+                dice => format!("{} ({})", self.hit_points, dice),
+            }),
+            property("Speed", self.speed.clone()),
+            Line::default(),
+            Line::from(
+                Ability::ALL.iter()
+                    .map(|&ability| {
+                        let score = self.score(ability);
+                        format!("{} {} ({:+})", ability.abbreviation().to_uppercase(), score, ability::modifier(score))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ")
+            ),
+            Line::default(),
+        ];
+
+        if !self.saving_throws.is_empty() {
+            let saves = self.saving_throws.iter()
+                .map(|(ability, modifier)| format!("{} {:+}", ability.abbreviation(), modifier))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(property("Saving Throws", saves));
+        }
+        for (name, value) in [
+            ("Skills", &self.skills),
+            ("Damage Vulnerabilities", &self.damage_vulnerabilities),
+            ("Damage Resistances", &self.damage_resistances),
+            ("Damage Immunities", &self.damage_immunities),
+            ("Condition Immunities", &self.condition_immunities),
+            ("Senses", &self.senses),
+            ("Languages", &self.languages),
+            ("Challenge", &self.challenge_rating),
+        ] {
+            if !value.is_empty() {
+                lines.push(property(name, value.clone()));
+            }
+        }
+
+        for (heading, features) in [
+            (None, &self.traits),
+            (Some("Actions"), &self.actions),
+            (Some("Reactions"), &self.reactions),
+            (Some("Legendary Actions"), &self.legendary_actions),
+        ] {
+            if features.is_empty() {
+                continue;
+            }
+            lines.push(Line::default());
+            if let Some(heading) = heading {
+                lines.push(Line::from(heading).bold().underlined());
+            }
+            if heading == Some("Legendary Actions") && !self.legendary_desc.is_empty() {
+                lines.push(Line::from(self.legendary_desc.as_str()));
+            }
+            for feature in features {
+                lines.push(Line::from(vec![
+                    Span::from(format!("{}. ", feature.name)).bold().italic(),
+                    Span::from(feature.desc.as_str()),
+                ]));
+            }
+        }
+        Text::from(lines)
+    }
+}
+
+/// A searchable collection of monster stat blocks.
+#[derive(Default)]
+pub struct Compendium {
+    /// The monsters, sorted by name.
+    monsters: Vec<StatBlock>,
+}
+
+impl Compendium {
+    /// Loads the bundled monsters and the user's monsters, if the user has any.
+    pub fn load() -> io::Result<Compendium> {
+        let mut monsters: Vec<StatBlock> = serde_json::from_str(BUNDLED)?;
+        if let Some(path) = Compendium::user_file() {
+            match fs::read_to_string(path) {
+                Ok(contents) => {
+                    let user: Vec<StatBlock> = serde_json::from_str(&contents)?;
+                    monsters.retain(|monster| !user.iter().any(|other| other.name == monster.name));
+                    monsters.extend(user);
+                },
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => return Err(err),
+            }
+        }
+        monsters.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Compendium { monsters })
+    }
+
+    /// Returns the path of the user's monster file, or [`None`] if no configuration directory
+    /// could be determined.
+    pub fn user_file() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("initiative-tracker").join("monsters.json"))
+    }
+
+    /// Returns the monsters whose name fuzzily matches the query, best match first.
+    pub fn search(&self, query: &str) -> Vec<&StatBlock> {
+        let mut matches = self.monsters.iter()
+            .filter_map(|monster| Some((fuzzy_score(query, &monster.name)?, monster)))
+            .collect::<Vec<_>>();
+        // sort is stable, so equally good matches stay in alphabetical order
+        matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+        matches.into_iter().map(|(_, monster)| monster).collect()
+    }
+}
+
+/// Scores how well the query matches the candidate, ignoring case, or returns [`None`] if the
+/// characters of the query do not all appear in the candidate in order.
+///
+/// Characters that follow the previous match directly, or that start a word, score higher.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let idx = next + candidate[next..].iter().position(|&other| other == c)?;
+        score += 1;
+        if idx == 0 || candidate[idx - 1] == ' ' {
+            score += 3;
+        }
+        if last_match.is_some_and(|last| last + 1 == idx) {
+            score += 2;
+        }
+        last_match = Some(idx);
+        next = idx + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(name: &str, desc: &str) -> Feature {
+        Feature { name: name.to_string(), desc: desc.to_string() }
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_runs() {
+        assert_eq!(fuzzy_score("gob", "Goblin"), Some(10));
+        // the first match is taken, so the "b" of "Goblin" is used rather than the start of "Boss"
+        assert_eq!(fuzzy_score("GB", "Goblin Boss"), Some(5));
+        assert!(fuzzy_score("gob", "Goblin") > fuzzy_score("gob", "Hobgoblin"));
+        assert!(fuzzy_score("adr", "Adult Red Dragon") > fuzzy_score("adr", "Young Adult Dragon"));
+        assert_eq!(fuzzy_score("bog", "Goblin"), None);
+    }
+
+    #[test]
+    fn features_parse_as_attacks() {
+        let bite = feature(
+            "Bite",
+            "Melee Weapon Attack: +11 to hit, reach 10 ft., one target. Hit: 17 (2d10 + 6) piercing damage plus 4 (1d8) acid damage.",
+        );
+        assert_eq!(bite.attack(), Some(Attack { name: "Bite".to_string(), bonus: 11, damage: "2d10+6 piercing plus 1d8 acid".to_string() }));
+
+        let breath = feature("Acid Breath (Recharge 5-6)", "The dragon exhales acid in a 60-foot line.");
+        assert_eq!(breath.attack(), None);
+    }
+
+    #[test]
+    fn stat_block_resources() {
+        let dragon: StatBlock = serde_json::from_value(serde_json::json!({
+            "name": "Dragon",
+            "armor_class": 18,
+            "hit_points": 178,
+            "traits": [
+                { "name": "Legendary Resistance (3/Day)" },
+                { "name": "Spellcasting", "desc": "1st level (4 slots): shield\n2nd level (2 slots): misty step" },
+            ],
+            "actions": [{ "name": "Acid Breath (Recharge 5-6)" }, { "name": "Frightful Presence (1/Day)" }],
+        })).unwrap();
+        assert_eq!(dragon.resources(), [
+            Resource::new(ResourceKind::SpellSlot(1), "", 4),
+            Resource::new(ResourceKind::SpellSlot(2), "", 2),
+            Resource::new(ResourceKind::LegendaryResistance, "", 3),
+            Resource::new(ResourceKind::Recharge(5), "Acid Breath", 1),
+            Resource::new(ResourceKind::PerDay, "Frightful Presence", 1),
+        ]);
+    }
+}
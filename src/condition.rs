@@ -0,0 +1,204 @@
+use crate::combatant::CombatantId;
+use serde::{Deserialize, Serialize};
+
+/// A condition that alters a combatant's capabilities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Condition {
+    /// Can't see, and automatically fails checks that require sight.
+    Blinded,
+
+    /// Can't attack the charmer or target them with harmful abilities.
+    Charmed,
+
+    /// Can't hear, and automatically fails checks that require hearing.
+    Deafened,
+
+    /// Exhaustion is measured in levels, from 1 to 6.
+    Exhaustion(u8),
+
+    /// Disadvantage on ability checks and attack rolls while the source of fear is in sight.
+    Frightened,
+
+    /// Speed becomes 0.
+    Grappled,
+
+    /// Can't take actions or reactions.
+    Incapacitated,
+
+    /// Impossible to see without special senses.
+    Invisible,
+
+    /// Incapacitated and can't move or speak.
+    Paralyzed,
+
+    /// Transformed into a solid inanimate substance.
+    Petrified,
+
+    /// Disadvantage on attack rolls and ability checks.
+    Poisoned,
+
+    /// Lying on the ground; can only crawl.
+    Prone,
+
+    /// Speed becomes 0, and attacks against the creature have advantage.
+    Restrained,
+
+    /// Incapacitated, can't move, and can speak only falteringly.
+    Stunned,
+
+    /// Incapacitated, can't move or speak, and is unaware of its surroundings.
+    Unconscious,
+}
+
+impl Condition {
+    /// All conditions, in alphabetical order. Exhaustion is listed at level 1.
+    pub const ALL: [Condition; 15] = [
+        Condition::Blinded,
+        Condition::Charmed,
+        Condition::Deafened,
+        Condition::Exhaustion(1),
+        Condition::Frightened,
+        Condition::Grappled,
+        Condition::Incapacitated,
+        Condition::Invisible,
+        Condition::Paralyzed,
+        Condition::Petrified,
+        Condition::Poisoned,
+        Condition::Prone,
+        Condition::Restrained,
+        Condition::Stunned,
+        Condition::Unconscious,
+    ];
+
+    /// The highest level of exhaustion; a creature at this level dies.
+    pub const MAX_EXHAUSTION: u8 = 6;
+
+    /// Returns true if the condition leaves the creature incapacitated, which ends its
+    /// concentration.
+    pub fn incapacitates(&self) -> bool {
+        matches!(
+            self,
+            Condition::Incapacitated | Condition::Paralyzed | Condition::Petrified | Condition::Stunned | Condition::Unconscious
+        )
+    }
+
+    /// Returns true if both conditions are the same kind of condition, ignoring exhaustion level.
+    pub fn same_kind(&self, other: &Condition) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Blinded => write!(f, "Blinded"),
+            Condition::Charmed => write!(f, "Charmed"),
+            Condition::Deafened => write!(f, "Deafened"),
+            Condition::Exhaustion(level) => write!(f, "Exhaustion {}", level),
+            Condition::Frightened => write!(f, "Frightened"),
+            Condition::Grappled => write!(f, "Grappled"),
+            Condition::Incapacitated => write!(f, "Incapacitated"),
+            Condition::Invisible => write!(f, "Invisible"),
+            Condition::Paralyzed => write!(f, "Paralyzed"),
+            Condition::Petrified => write!(f, "Petrified"),
+            Condition::Poisoned => write!(f, "Poisoned"),
+            Condition::Prone => write!(f, "Prone"),
+            Condition::Restrained => write!(f, "Restrained"),
+            Condition::Stunned => write!(f, "Stunned"),
+            Condition::Unconscious => write!(f, "Unconscious"),
+        }
+    }
+}
+
+/// How long a condition lasts before it expires on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Duration {
+    /// The condition lasts for the given number of rounds, counting down at the end of each of the
+    /// affected combatant's turns.
+    Rounds(u32),
+
+    /// The condition lasts until the end of the source combatant's next turn.
+    ///
+    /// `turns` is the number of the source's turns that must end before the condition expires; it
+    /// is 2 if the condition was applied during the source's own turn, since that turn is not the
+    /// source's "next" turn.
+    EndOfSourceTurn {
+        source: CombatantId,
+        turns: u32,
+    },
+}
+
+/// A condition applied to a combatant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedCondition {
+    /// The condition.
+    pub condition: Condition,
+
+    /// How long the condition lasts, or [`None`] if it lasts until removed.
+    #[serde(default)]
+    pub duration: Option<Duration>,
+
+    /// The combatant whose concentration maintains the condition, if it comes from a
+    /// concentration spell. The condition ends when their concentration does.
+    #[serde(default)]
+    pub concentration: Option<CombatantId>,
+}
+
+impl AppliedCondition {
+    /// Counts down the duration of the condition at the end of a combatant's turn.
+    ///
+    /// `bearer` is the combatant affected by the condition, and `ended` is the combatant whose turn
+    /// just ended. Returns true if the condition has expired.
+    pub fn end_turn(&mut self, bearer: CombatantId, ended: CombatantId) -> bool {
+        match &mut self.duration {
+            Some(Duration::Rounds(rounds)) if bearer == ended => {
+                *rounds = rounds.saturating_sub(1);
+                *rounds == 0
+            },
+            Some(Duration::EndOfSourceTurn { source, turns }) if *source == ended => {
+                *turns = turns.saturating_sub(1);
+                *turns == 0
+            },
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for AppliedCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.duration {
+            Some(Duration::Rounds(rounds)) => write!(f, "{} ({} rnd)", self.condition, rounds),
+            Some(Duration::EndOfSourceTurn { .. }) => write!(f, "{} (eot)", self.condition),
+            None if self.concentration.is_some() => write!(f, "{} (conc)", self.condition),
+            None => write!(f, "{}", self.condition),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(duration: Duration) -> AppliedCondition {
+        AppliedCondition { condition: Condition::Prone, duration: Some(duration), concentration: None }
+    }
+
+    #[test]
+    fn rounds_count_down_on_the_bearers_turn() {
+        let (bearer, other) = (CombatantId(0), CombatantId(1));
+        let mut condition = applied(Duration::Rounds(2));
+        assert!(!condition.end_turn(bearer, other));
+        assert!(!condition.end_turn(bearer, bearer));
+        assert_eq!(condition.duration, Some(Duration::Rounds(1)));
+        assert!(condition.end_turn(bearer, bearer));
+    }
+
+    #[test]
+    fn end_of_source_turn_counts_the_sources_turns() {
+        let (bearer, source) = (CombatantId(0), CombatantId(1));
+        let mut condition = applied(Duration::EndOfSourceTurn { source, turns: 2 });
+        assert!(!condition.end_turn(bearer, bearer));
+        assert!(!condition.end_turn(bearer, source));
+        assert!(condition.end_turn(bearer, source));
+    }
+}
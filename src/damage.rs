@@ -0,0 +1,354 @@
+use crate::dice::{Expression, ParseError, Rng, Roll};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A type of damage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DamageType {
+    Acid,
+    Bludgeoning,
+    Cold,
+    Fire,
+    Force,
+    Lightning,
+    Necrotic,
+    Piercing,
+    Poison,
+    Psychic,
+    Radiant,
+    Slashing,
+    Thunder,
+}
+
+impl DamageType {
+    /// All damage types, in alphabetical order.
+    pub const ALL: [DamageType; 13] = [
+        DamageType::Acid,
+        DamageType::Bludgeoning,
+        DamageType::Cold,
+        DamageType::Fire,
+        DamageType::Force,
+        DamageType::Lightning,
+        DamageType::Necrotic,
+        DamageType::Piercing,
+        DamageType::Poison,
+        DamageType::Psychic,
+        DamageType::Radiant,
+        DamageType::Slashing,
+        DamageType::Thunder,
+    ];
+
+    /// Returns the name of the damage type, in lowercase as it appears in rules text.
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageType::Acid => "acid",
+            DamageType::Bludgeoning => "bludgeoning",
+            DamageType::Cold => "cold",
+            DamageType::Fire => "fire",
+            DamageType::Force => "force",
+            DamageType::Lightning => "lightning",
+            DamageType::Necrotic => "necrotic",
+            DamageType::Piercing => "piercing",
+            DamageType::Poison => "poison",
+            DamageType::Psychic => "psychic",
+            DamageType::Radiant => "radiant",
+            DamageType::Slashing => "slashing",
+            DamageType::Thunder => "thunder",
+        }
+    }
+
+    /// Parses a damage type from its name, ignoring case.
+    pub fn parse(name: &str) -> Option<DamageType> {
+        let name = name.trim().to_ascii_lowercase();
+        DamageType::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Returns true for the physical damage types, which can come from magical or nonmagical
+    /// attacks.
+    pub fn is_physical(self) -> bool {
+        matches!(self, DamageType::Bludgeoning | DamageType::Piercing | DamageType::Slashing)
+    }
+}
+
+impl fmt::Display for DamageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A damage type listed in a combatant's resistances, vulnerabilities or immunities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DamageEntry {
+    /// The damage type.
+    pub kind: DamageType,
+
+    /// Whether only damage from nonmagical attacks is affected, as in "bludgeoning, piercing, and
+    /// slashing from nonmagical attacks".
+    #[serde(default)]
+    pub nonmagical_only: bool,
+}
+
+impl DamageEntry {
+    /// Returns true if the entry applies to damage of the given type.
+    pub fn applies(&self, kind: DamageType, magical: bool) -> bool {
+        self.kind == kind && !(self.nonmagical_only && magical)
+    }
+
+    /// Parses a list of damage types, either as written in a stat block ("cold; bludgeoning,
+    /// piercing, and slashing from nonmagical attacks") or as formatted by [`format_list`] ("cold,
+    /// nonmagical slashing").
+    pub fn parse_list(list: &str) -> Result<Vec<DamageEntry>, String> {
+        let (entries, unknown) = DamageEntry::parse_known(list);
+        match unknown.first() {
+            Some(word) => Err(format!("unknown damage type \"{}\"", word)),
+            None => Ok(entries),
+        }
+    }
+
+    /// Parses a list of damage types like [`DamageEntry::parse_list`], but leaves out the words
+    /// that aren't damage types instead of failing, returning them after the entries.
+    pub fn parse_known(list: &str) -> (Vec<DamageEntry>, Vec<String>) {
+        let mut entries = Vec::new();
+        let mut unknown = Vec::new();
+        for segment in list.split(';') {
+            // everything in a segment ending "from nonmagical attacks" is nonmagical only
+            let (types, segment_nonmagical) = match segment.split_once(" from ") {
+                Some((types, source)) => (types, source.contains("nonmagical")),
+                None => (segment, false),
+            };
+            for item in types.split(',') {
+                let mut nonmagical = segment_nonmagical;
+                for word in item.split_whitespace() {
+                    match word.to_ascii_lowercase().as_str() {
+                        "and" => (),
+                        "nonmagical" => nonmagical = true,
+                        word => match DamageType::parse(word) {
+                            Some(kind) => entries.push(DamageEntry { kind, nonmagical_only: nonmagical && kind.is_physical() }),
+                            None => unknown.push(word.to_string()),
+                        },
+                    }
+                }
+            }
+        }
+        (entries, unknown)
+    }
+}
+
+/// Formats a list of damage types, e.g. "cold, nonmagical slashing".
+pub fn format_list(entries: &[DamageEntry]) -> String {
+    entries.iter()
+        .map(|entry| if entry.nonmagical_only {
+            format!("nonmagical {}", entry.kind)
+        } else {
+            entry.kind.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// How a combatant's resistances, vulnerabilities and immunities changed an amount of damage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageModifier {
+    Normal,
+    Resistant,
+    Vulnerable,
+    Immune,
+}
+
+impl DamageModifier {
+    /// Applies the modifier to an amount of damage.
+    pub fn apply(self, amount: i32) -> i32 {
+        match self {
+            DamageModifier::Normal => amount,
+            DamageModifier::Resistant => amount / 2,
+            DamageModifier::Vulnerable => amount.saturating_mul(2),
+            DamageModifier::Immune => 0,
+        }
+    }
+
+    /// Returns a description of the modifier, as shown in the log.
+    pub fn description(self) -> &'static str {
+        match self {
+            DamageModifier::Normal => "",
+            DamageModifier::Resistant => "resistant, halved",
+            DamageModifier::Vulnerable => "vulnerable, doubled",
+            DamageModifier::Immune => "immune",
+        }
+    }
+}
+
+/// An amount of damage of a single type, dealt to a combatant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DamageInstance {
+    /// The amount of damage, before resistances.
+    pub amount: i32,
+
+    /// The type of the damage, or [`None`] if it has no particular type.
+    pub kind: Option<DamageType>,
+
+    /// Whether the damage comes from a magical attack or effect.
+    pub magical: bool,
+}
+
+impl DamageInstance {
+    /// Returns the damage with its amount halved, as taken on a successful saving throw.
+    pub fn halved(self) -> DamageInstance {
+        DamageInstance { amount: self.amount / 2, ..self }
+    }
+}
+
+/// Describes the damage, e.g. "14 magical slashing".
+impl fmt::Display for DamageInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.amount)?;
+        if self.magical && self.kind.is_some_and(DamageType::is_physical) {
+            write!(f, " magical")?;
+        }
+        if let Some(kind) = self.kind {
+            write!(f, " {}", kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// Dice of damage of a single type, such as `1d8+3 slashing`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedDamage {
+    /// The damage dice.
+    pub expression: Expression,
+
+    /// The type of the damage, or [`None`] if it has no particular type.
+    pub kind: Option<DamageType>,
+
+    /// Whether the damage comes from a magical attack or effect.
+    pub magical: bool,
+}
+
+impl TypedDamage {
+    /// Parses damage of one or more types, such as `8d6 fire` or `2d10+6 piercing plus 1d8
+    /// acid`. Each group of dice is followed by its type, optionally preceded by `magical`.
+    pub fn parse_all(input: &str) -> Result<Vec<TypedDamage>, String> {
+        let mut damage = Vec::new();
+        let mut expression = String::new();
+        let mut magical = false;
+        let mut finish = |expression: &mut String, kind, magical: &mut bool| -> Result<(), String> {
+            if expression.is_empty() {
+                return Err("expected damage dice before the damage type, e.g. 1d8+3 slashing".to_string());
+            }
+            let parsed = expression.parse().map_err(|error: ParseError| error.to_string())?;
+            damage.push(TypedDamage { expression: parsed, kind, magical: *magical });
+            expression.clear();
+            *magical = false;
+            Ok(())
+        };
+        for word in input.split_whitespace() {
+            match word.to_ascii_lowercase().as_str() {
+                "plus" => (),
+                "magical" => magical = true,
+                _ => match DamageType::parse(word) {
+                    Some(kind) => finish(&mut expression, Some(kind), &mut magical)?,
+                    None => expression.push_str(word),
+                },
+            }
+        }
+        if !expression.is_empty() || magical {
+            finish(&mut expression, None, &mut magical)?;
+        }
+        if damage.is_empty() {
+            return Err("expected damage, e.g. 1d8+3 slashing".to_string());
+        }
+        Ok(damage)
+    }
+
+    /// Rolls the damage, doubling the dice for a critical hit.
+    pub fn roll(&self, rng: &mut Rng, critical: bool) -> (Roll, DamageInstance) {
+        let roll = if critical {
+            self.expression.critical().roll(rng)
+        } else {
+            self.expression.roll(rng)
+        };
+        let instance = DamageInstance { amount: roll.total().max(0), kind: self.kind, magical: self.magical };
+        (roll, instance)
+    }
+}
+
+/// Formats damage rolls and their types, e.g. `2d10+6: [4, 9]+6 = 19 piercing, 1d8: [3] = 3 acid`.
+pub fn format_rolls(rolls: &[(Roll, DamageInstance)]) -> String {
+    rolls.iter()
+        .map(|(roll, instance)| match instance.kind {
+            Some(kind) => format!("{} {}", roll, kind),
+            None => roll.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_damage_types_are_left_out() {
+        let (entries, unknown) = DamageEntry::parse_known("cold; damage from spells");
+        assert_eq!(entries, [DamageEntry { kind: DamageType::Cold, nonmagical_only: false }]);
+        assert_eq!(unknown, ["damage"]);
+        assert_eq!(DamageEntry::parse_list("cold, spells"), Err("unknown damage type \"spells\"".to_string()));
+    }
+
+    #[test]
+    fn modifiers_halve_double_and_ignore_damage() {
+        assert_eq!(DamageModifier::Normal.apply(15), 15);
+        assert_eq!(DamageModifier::Resistant.apply(15), 7);
+        assert_eq!(DamageModifier::Vulnerable.apply(15), 30);
+        assert_eq!(DamageModifier::Vulnerable.apply(i32::MAX), i32::MAX);
+        assert_eq!(DamageModifier::Immune.apply(15), 0);
+    }
+
+    #[test]
+    fn stat_block_lists_parse() {
+        let entries = DamageEntry::parse_list("fire; bludgeoning, piercing, and slashing from nonmagical attacks").unwrap();
+        assert_eq!(format_list(&entries), "fire, nonmagical bludgeoning, nonmagical piercing, nonmagical slashing");
+        assert_eq!(DamageEntry::parse_list(&format_list(&entries)).unwrap(), entries);
+
+        // only physical damage can be nonmagical
+        let entries = DamageEntry::parse_list("nonmagical fire, Slashing").unwrap();
+        assert_eq!(format_list(&entries), "fire, slashing");
+        assert_eq!(DamageEntry::parse_list("").unwrap(), []);
+    }
+
+    #[test]
+    fn nonmagical_entries_only_apply_to_nonmagical_damage() {
+        let entry = DamageEntry { kind: DamageType::Slashing, nonmagical_only: true };
+        assert!(entry.applies(DamageType::Slashing, false));
+        assert!(!entry.applies(DamageType::Slashing, true));
+        assert!(!entry.applies(DamageType::Piercing, false));
+
+        let entry = DamageEntry { kind: DamageType::Slashing, nonmagical_only: false };
+        assert!(entry.applies(DamageType::Slashing, true));
+    }
+
+    #[test]
+    fn damage_of_several_types_parses() {
+        let damage = TypedDamage::parse_all("2d10+6 magical piercing plus 1d8 Acid").unwrap();
+        assert_eq!(damage.len(), 2);
+        assert_eq!((damage[0].expression.to_string(), damage[0].kind, damage[0].magical), ("2d10+6".to_string(), Some(DamageType::Piercing), true));
+        assert_eq!((damage[1].expression.to_string(), damage[1].kind, damage[1].magical), ("1d8".to_string(), Some(DamageType::Acid), false));
+
+        let untyped = TypedDamage::parse_all("3d6 + 2").unwrap();
+        assert_eq!((untyped[0].expression.to_string(), untyped[0].kind), ("3d6+2".to_string(), None));
+
+        assert!(TypedDamage::parse_all("fire").is_err());
+        assert!(TypedDamage::parse_all("").is_err());
+        assert!(TypedDamage::parse_all("2d6 fire plus 1dx cold").is_err());
+    }
+
+    #[test]
+    fn halved_damage_rounds_down() {
+        let instance = DamageInstance { amount: 27, kind: Some(DamageType::Fire), magical: true };
+        assert_eq!(instance.halved(), DamageInstance { amount: 13, ..instance });
+        assert_eq!(instance.to_string(), "27 fire");
+        let instance = DamageInstance { amount: 9, kind: Some(DamageType::Slashing), magical: true };
+        assert_eq!(instance.to_string(), "9 magical slashing");
+    }
+}
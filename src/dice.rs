@@ -0,0 +1,640 @@
+use std::{fmt, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
+
+/// The maximum number of dice in a single group, to keep rolls fast and breakdowns readable.
+pub const MAX_DICE: u32 = 100;
+
+/// The maximum number of sides a die can have.
+pub const MAX_SIDES: u32 = 1000;
+
+/// The largest constant allowed in an expression, so that totals stay far from overflowing.
+pub const MAX_CONSTANT: u32 = 1_000_000;
+
+/// A small, seedable pseudo-random number generator (SplitMix64).
+///
+/// The same seed always produces the same sequence of rolls, which makes rolls reproducible.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    /// Creates a generator seeded from the current time.
+    fn default() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        Rng::seeded(nanos)
+    }
+}
+
+impl Rng {
+    /// Creates a generator with the given seed.
+    pub fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Returns the next pseudo-random 64-bit number.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Rolls a die with the given number of sides, returning a number from 1 to `sides`.
+    pub fn roll(&mut self, sides: u32) -> u32 {
+        // rejection sampling avoids bias towards low numbers
+        let sides = u64::from(sides.max(1));
+        let zone = u64::MAX - u64::MAX % sides;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % sides) as u32 + 1;
+            }
+        }
+    }
+}
+
+/// Which dice of a group to keep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep the given number of highest dice.
+    Highest(u32),
+
+    /// Keep the given number of lowest dice.
+    Lowest(u32),
+}
+
+/// Whether a d20 is rolled normally, with advantage, or with disadvantage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RollMode {
+    #[default]
+    Normal,
+
+    /// Roll two d20s and keep the highest.
+    Advantage,
+
+    /// Roll two d20s and keep the lowest.
+    Disadvantage,
+}
+
+impl RollMode {
+    /// Combines two sources of advantage or disadvantage. Advantage and disadvantage cancel out.
+    pub fn combine(self, other: RollMode) -> RollMode {
+        match (self, other) {
+            (RollMode::Normal, mode) | (mode, RollMode::Normal) => mode,
+            (a, b) if a == b => a,
+            _ => RollMode::Normal,
+        }
+    }
+
+    /// Returns the next mode, cycling back to the first.
+    pub fn next(self) -> RollMode {
+        match self {
+            RollMode::Normal => RollMode::Advantage,
+            RollMode::Advantage => RollMode::Disadvantage,
+            RollMode::Disadvantage => RollMode::Normal,
+        }
+    }
+
+    /// Returns a short label for the mode, which is empty for a normal roll.
+    pub fn label(self) -> &'static str {
+        match self {
+            RollMode::Normal => "",
+            RollMode::Advantage => "adv",
+            RollMode::Disadvantage => "dis",
+        }
+    }
+}
+
+/// A single term of a dice expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Term {
+    /// A constant number.
+    Constant(i32),
+
+    /// A group of dice.
+    Dice {
+        /// The number of dice to roll.
+        count: u32,
+
+        /// The number of sides of each die.
+        sides: u32,
+
+        /// Which dice to keep, or [`None`] to keep all of them.
+        keep: Option<Keep>,
+    },
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Constant(value) => write!(f, "{}", value),
+            Term::Dice { count, sides, keep } => {
+                write!(f, "{}d{}", count, sides)?;
+                match keep {
+                    Some(Keep::Highest(n)) => write!(f, "kh{}", n),
+                    Some(Keep::Lowest(n)) => write!(f, "kl{}", n),
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+}
+
+/// An error encountered while parsing a dice expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The expression is empty.
+    Empty,
+
+    /// A number was expected at the given position.
+    ExpectedNumber(usize),
+
+    /// An unexpected character was found at the given position.
+    UnexpectedChar(usize, char),
+
+    /// A number is too large.
+    TooLarge,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty expression"),
+            ParseError::ExpectedNumber(pos) => write!(f, "expected a number at position {}", pos + 1),
+            ParseError::UnexpectedChar(pos, c) => write!(f, "unexpected `{}` at position {}", c, pos + 1),
+            ParseError::TooLarge => write!(
+                f,
+                "number too large (at most {} dice with {} sides, and constants up to {})",
+                MAX_DICE,
+                MAX_SIDES,
+                MAX_CONSTANT,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A dice expression, such as `1d20+3`, `2d6+1d4-1`, or `4d6kh3`.
+///
+/// An expression is a sum of terms, where each term is either a constant or a group of dice. A
+/// group of dice is written `NdS`, where `N` is the number of dice (1 if omitted) and `S` is the
+/// number of sides. A group can keep only its highest or lowest dice with a `khK` or `klK`
+/// suffix, so advantage is written `2d20kh1` and disadvantage `2d20kl1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expression {
+    /// The terms of the expression, and whether each is negated.
+    pub terms: Vec<(bool, Term)>,
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (negative, term)) in self.terms.iter().enumerate() {
+            match (i, negative) {
+                (0, false) => (),
+                (_, true) => write!(f, "-")?,
+                (_, false) => write!(f, "+")?,
+            }
+            write!(f, "{}", term)?;
+        }
+        Ok(())
+    }
+}
+
+/// A simple recursive descent parser over the characters of an expression, ignoring whitespace.
+struct Parser {
+    /// The characters of the expression and their byte positions, excluding whitespace.
+    chars: Vec<(usize, char)>,
+
+    /// The index of the next character to parse.
+    next: usize,
+
+    /// The length of the expression, used as the position of the end of the input.
+    len: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> Parser {
+        Parser {
+            chars: input.char_indices().filter(|(_, c)| !c.is_whitespace()).collect(),
+            next: 0,
+            len: input.len(),
+        }
+    }
+
+    /// Returns the next character without consuming it.
+    fn peek(&self) -> Option<(usize, char)> {
+        self.chars.get(self.next).copied()
+    }
+
+    /// Consumes and returns the next character.
+    fn bump(&mut self) -> Option<(usize, char)> {
+        let next = self.peek()?;
+        self.next += 1;
+        Some(next)
+    }
+
+    /// Returns the position of the next character.
+    fn pos(&self) -> usize {
+        self.peek().map(|(pos, _)| pos).unwrap_or(self.len)
+    }
+
+    /// Consumes the next character if it is the given character.
+    fn eat(&mut self, expected: char) -> bool {
+        match self.peek() {
+            Some((_, c)) if c.eq_ignore_ascii_case(&expected) => {
+                self.next += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Parses a non-negative number, if there is one.
+    fn number(&mut self) -> Result<Option<u32>, ParseError> {
+        let mut value = None::<u32>;
+        while let Some(digit) = self.peek().and_then(|(_, c)| c.to_digit(10)) {
+            self.next += 1;
+            value = Some(
+                value.unwrap_or_default()
+                    .checked_mul(10)
+                    .and_then(|value| value.checked_add(digit))
+                    .filter(|&value| value <= MAX_CONSTANT)
+                    .ok_or(ParseError::TooLarge)?
+            );
+        }
+        Ok(value)
+    }
+
+    /// Parses a number that must be present.
+    fn required_number(&mut self) -> Result<u32, ParseError> {
+        let pos = self.pos();
+        self.number()?.ok_or(ParseError::ExpectedNumber(pos))
+    }
+
+    /// Parses a single term.
+    fn term(&mut self) -> Result<Term, ParseError> {
+        let pos = self.pos();
+        let count = self.number()?;
+        if !self.eat('d') {
+            return count
+                .map(|value| Term::Constant(value as i32))
+                .ok_or(ParseError::ExpectedNumber(pos));
+        }
+
+        let count = count.unwrap_or(1);
+        let sides_pos = self.pos();
+        let sides = self.required_number()?;
+        if count > MAX_DICE || sides > MAX_SIDES {
+            return Err(ParseError::TooLarge);
+        }
+        if sides == 0 {
+            return Err(ParseError::ExpectedNumber(sides_pos));
+        }
+
+        let keep = if self.eat('k') {
+            if self.eat('h') {
+                Some(Keep::Highest(self.required_number()?.min(count)))
+            } else if self.eat('l') {
+                Some(Keep::Lowest(self.required_number()?.min(count)))
+            } else {
+                // `4d6k3` is shorthand for keeping the highest
+                Some(Keep::Highest(self.required_number()?.min(count)))
+            }
+        } else {
+            None
+        };
+        Ok(Term::Dice { count, sides, keep })
+    }
+
+    /// Parses a full expression.
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        if self.peek().is_none() {
+            return Err(ParseError::Empty);
+        }
+
+        let mut terms = Vec::new();
+        let mut negative = self.eat('-');
+        if !negative {
+            self.eat('+');
+        }
+        loop {
+            terms.push((negative, self.term()?));
+            negative = match self.bump() {
+                Some((_, '+')) => false,
+                Some((_, '-')) => true,
+                Some((pos, c)) => return Err(ParseError::UnexpectedChar(pos, c)),
+                None => break,
+            };
+        }
+        Ok(Expression { terms })
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Expression, ParseError> {
+        Parser::new(s).expression()
+    }
+}
+
+impl Expression {
+    /// Returns the expression for a d20 roll with the given modifier, such as an ability check,
+    /// saving throw, or attack roll.
+    pub fn d20(modifier: i32, mode: RollMode) -> Expression {
+        let keep = match mode {
+            RollMode::Normal => None,
+            RollMode::Advantage => Some(Keep::Highest(1)),
+            RollMode::Disadvantage => Some(Keep::Lowest(1)),
+        };
+        let count = if keep.is_some() { 2 } else { 1 };
+        let mut terms = vec![(false, Term::Dice { count, sides: 20, keep })];
+        if modifier != 0 {
+            terms.push((modifier < 0, Term::Constant(modifier.abs())));
+        }
+        Expression { terms }
+    }
+
+    /// Returns the expression with the number of dice doubled, as rolled for the damage of a
+    /// critical hit.
+    pub fn critical(&self) -> Expression {
+        let terms = self.terms.iter()
+            .map(|&(negative, term)| match term {
+                Term::Dice { count, sides, keep: None } => (negative, Term::Dice { count: count * 2, sides, keep: None }),
+                term => (negative, term),
+            })
+            .collect();
+        Expression { terms }
+    }
+
+    /// Rolls the expression.
+    pub fn roll(&self, rng: &mut Rng) -> Roll {
+        let terms = self.terms.iter()
+            .map(|&(negative, term)| {
+                let rolled = match term {
+                    Term::Constant(value) => RolledTerm::Constant(value),
+                    Term::Dice { count, sides, keep } => {
+                        let values = (0..count).map(|_| rng.roll(sides)).collect::<Vec<_>>();
+
+                        // mark the dice to keep, preferring earlier dice on ties
+                        let mut order = (0..values.len()).collect::<Vec<_>>();
+                        let keep_count = match keep {
+                            Some(Keep::Highest(n)) => {
+                                order.sort_by_key(|&i| std::cmp::Reverse(values[i]));
+                                n as usize
+                            },
+                            Some(Keep::Lowest(n)) => {
+                                order.sort_by_key(|&i| values[i]);
+                                n as usize
+                            },
+                            None => values.len(),
+                        };
+                        let mut kept = vec![false; values.len()];
+                        for &i in order.iter().take(keep_count) {
+                            kept[i] = true;
+                        }
+
+                        RolledTerm::Dice {
+                            sides,
+                            dice: values.into_iter().zip(kept).collect(),
+                        }
+                    },
+                };
+                (negative, rolled)
+            })
+            .collect();
+        Roll { expression: self.clone(), terms }
+    }
+}
+
+/// The result of rolling a single term of an expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RolledTerm {
+    /// A constant number.
+    Constant(i32),
+
+    /// A group of dice, with the value of each die and whether it was kept.
+    Dice {
+        sides: u32,
+        dice: Vec<(u32, bool)>,
+    },
+}
+
+impl RolledTerm {
+    /// Returns the value of the term.
+    pub fn value(&self) -> i32 {
+        match self {
+            RolledTerm::Constant(value) => *value,
+            RolledTerm::Dice { dice, .. } => dice.iter()
+                .filter(|(_, kept)| *kept)
+                .map(|&(value, _)| value as i32)
+                .sum(),
+        }
+    }
+}
+
+/// The result of rolling a dice expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Roll {
+    /// The expression that was rolled.
+    pub expression: Expression,
+
+    /// The result of each term, and whether it was negated.
+    pub terms: Vec<(bool, RolledTerm)>,
+}
+
+impl Roll {
+    /// Returns the kept value of the first d20 rolled, e.g. to check for a natural 20 or 1.
+    pub fn natural(&self) -> Option<u32> {
+        self.terms.iter().find_map(|(_, term)| match term {
+            RolledTerm::Dice { sides: 20, dice } => dice.iter()
+                .find(|(_, kept)| *kept)
+                .map(|&(value, _)| value),
+            _ => None,
+        })
+    }
+
+    /// Returns the total of the roll. Very long expressions saturate instead of overflowing.
+    pub fn total(&self) -> i32 {
+        self.terms.iter()
+            .map(|(negative, term)| if *negative { -term.value() } else { term.value() })
+            .fold(0, i32::saturating_add)
+    }
+}
+
+/// Displays the breakdown of the roll, e.g. `1d20+3: [14]+3 = 17`. Dropped dice are shown in
+/// parentheses.
+impl fmt::Display for Roll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.expression)?;
+        for (i, (negative, term)) in self.terms.iter().enumerate() {
+            match (i, negative) {
+                (0, false) => (),
+                (_, true) => write!(f, "-")?,
+                (_, false) => write!(f, "+")?,
+            }
+            match term {
+                RolledTerm::Constant(value) => write!(f, "{}", value)?,
+                RolledTerm::Dice { dice, .. } => {
+                    let dice = dice.iter()
+                        .map(|(value, kept)| if *kept {
+                            value.to_string()
+                        } else {
+                            format!("({})", value)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    write!(f, "[{}]", dice)?;
+                },
+            }
+        }
+        write!(f, " = {}", self.total())
+    }
+}
+
+/// Parses and rolls a dice expression.
+pub fn roll(input: &str, rng: &mut Rng) -> Result<Roll, ParseError> {
+    Ok(input.parse::<Expression>()?.roll(rng))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a generator with a fixed seed, so that every run rolls the same dice.
+    fn rng() -> Rng {
+        Rng::seeded(0x5eed)
+    }
+
+    fn parse(input: &str) -> Expression {
+        input.parse().unwrap()
+    }
+
+    fn dice(count: u32, sides: u32, keep: Option<Keep>) -> Term {
+        Term::Dice { count, sides, keep }
+    }
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let expression = parse("10d20");
+        assert_eq!(expression.roll(&mut rng()), expression.roll(&mut rng()));
+    }
+
+    #[test]
+    fn d20_plus_modifier() {
+        let expression = parse("1d20+3");
+        assert_eq!(expression.terms, vec![(false, dice(1, 20, None)), (false, Term::Constant(3))]);
+        let mut rng = rng();
+        for _ in 0..100 {
+            let roll = expression.roll(&mut rng);
+            let natural = roll.natural().unwrap();
+            assert!((1..=20).contains(&natural));
+            assert_eq!(roll.total(), natural as i32 + 3);
+        }
+    }
+
+    #[test]
+    fn several_groups_and_constants() {
+        let expression = parse("2d6 + 1d4 - 1");
+        assert_eq!(expression.terms, vec![
+            (false, dice(2, 6, None)),
+            (false, dice(1, 4, None)),
+            (true, Term::Constant(1)),
+        ]);
+        assert_eq!(expression.to_string(), "2d6+1d4-1");
+        let mut rng = rng();
+        for _ in 0..100 {
+            assert!((2..=15).contains(&expression.roll(&mut rng).total()));
+        }
+    }
+
+    #[test]
+    fn keep_highest() {
+        let expression = parse("4d6kh3");
+        assert_eq!(expression.terms, vec![(false, dice(4, 6, Some(Keep::Highest(3))))]);
+        let mut rng = rng();
+        for _ in 0..100 {
+            let roll = expression.roll(&mut rng);
+            let RolledTerm::Dice { dice, .. } = &roll.terms[0].1 else {
+                panic!("expected dice");
+            };
+            let mut values = dice.iter().map(|&(value, _)| value as i32).collect::<Vec<_>>();
+            values.sort();
+            assert_eq!(dice.iter().filter(|(_, kept)| *kept).count(), 3);
+            assert_eq!(roll.total(), values[1..].iter().sum::<i32>());
+        }
+    }
+
+    #[test]
+    fn advantage_and_disadvantage() {
+        let mut rng = rng();
+        for (input, keep_highest) in [("2d20kh1", true), ("2d20kl1", false)] {
+            let expression = parse(input);
+            for _ in 0..100 {
+                let roll = expression.roll(&mut rng);
+                let RolledTerm::Dice { dice, .. } = &roll.terms[0].1 else {
+                    panic!("expected dice");
+                };
+                let (a, b) = (dice[0].0, dice[1].0);
+                let expected = if keep_highest { a.max(b) } else { a.min(b) };
+                assert_eq!(roll.natural(), Some(expected));
+                assert_eq!(roll.total(), expected as i32);
+            }
+        }
+        assert_eq!(Expression::d20(2, RollMode::Advantage), parse("2d20kh1+2"));
+        assert_eq!(Expression::d20(-1, RollMode::Disadvantage), parse("2d20kl1-1"));
+    }
+
+    #[test]
+    fn leading_minus() {
+        let expression = parse("-1d4+2");
+        assert_eq!(expression.terms, vec![(true, dice(1, 4, None)), (false, Term::Constant(2))]);
+        assert_eq!(expression.to_string(), "-1d4+2");
+        let mut rng = rng();
+        for _ in 0..100 {
+            assert!((-2..=1).contains(&expression.roll(&mut rng).total()));
+        }
+    }
+
+    #[test]
+    fn keep_shorthand() {
+        assert_eq!(parse("4d6k3"), parse("4d6kh3"));
+        assert_eq!(parse("d20"), parse("1d20"));
+    }
+
+    #[test]
+    fn rejects_zero_sides_and_too_many_dice() {
+        assert_eq!("1d0".parse::<Expression>(), Err(ParseError::ExpectedNumber(2)));
+        assert_eq!("101d6".parse::<Expression>(), Err(ParseError::TooLarge));
+        assert_eq!("1d1001".parse::<Expression>(), Err(ParseError::TooLarge));
+        assert!(format!("{}d{}", MAX_DICE, MAX_SIDES).parse::<Expression>().is_ok());
+    }
+
+    #[test]
+    fn rejects_large_constants() {
+        assert_eq!("2147483647+1".parse::<Expression>(), Err(ParseError::TooLarge));
+        assert!(MAX_CONSTANT.to_string().parse::<Expression>().is_ok());
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!("".parse::<Expression>(), Err(ParseError::Empty));
+        assert_eq!("1d20+".parse::<Expression>(), Err(ParseError::ExpectedNumber(5)));
+        assert_eq!("1d20x".parse::<Expression>(), Err(ParseError::UnexpectedChar(4, 'x')));
+        assert_eq!("1d".parse::<Expression>(), Err(ParseError::ExpectedNumber(2)));
+        assert_eq!("4d6k".parse::<Expression>(), Err(ParseError::ExpectedNumber(4)));
+        // positions count the whitespace that is skipped
+        assert_eq!("1d6 + x".parse::<Expression>(), Err(ParseError::ExpectedNumber(6)));
+    }
+
+    #[test]
+    fn critical_doubles_dice_without_keep() {
+        let expression = parse("2d6+1d8+2d20kh1+3");
+        assert_eq!(expression.critical(), parse("4d6+2d8+2d20kh1+3"));
+    }
+}
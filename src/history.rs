@@ -0,0 +1,120 @@
+use crate::combatant::Combatant;
+use serde::{Deserialize, Serialize};
+
+/// A copy of the state of the encounter at some point in time.
+///
+/// This is also the format in which encounters are saved to files.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The combatants of the encounter, ordered by initiative.
+    pub combatants: Vec<Combatant>,
+
+    /// The index of the combatant whose turn it is.
+    #[serde(default)]
+    pub turn: Option<usize>,
+
+    /// The current round of combat.
+    #[serde(default)]
+    pub round: u32,
+}
+
+/// A change made to the encounter, which can be reversed by restoring the snapshot taken before
+/// (when undoing) or after (when redoing) the change.
+struct Step {
+    /// A short description of the change.
+    description: String,
+
+    /// The state of the encounter on the other side of the change.
+    snapshot: Snapshot,
+}
+
+/// The undo / redo history of the encounter.
+#[derive(Default)]
+pub struct History {
+    /// Changes that can be undone, most recent last.
+    undo: Vec<Step>,
+
+    /// Changes that were undone and can be redone, most recently undone last.
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Records a change that is about to be made to the encounter, given the state of the
+    /// encounter before the change.
+    ///
+    /// This clears the redo history, since it no longer follows from the current state.
+    pub fn record(&mut self, description: String, before: Snapshot) {
+        self.undo.push(Step { description, snapshot: before });
+        self.redo.clear();
+    }
+
+    /// Undoes the most recent change, given the current state of the encounter.
+    ///
+    /// Returns the description of the undone change and the state to restore.
+    pub fn undo(&mut self, current: Snapshot) -> Option<(String, Snapshot)> {
+        let step = self.undo.pop()?;
+        self.redo.push(Step { description: step.description.clone(), snapshot: current });
+        Some((step.description, step.snapshot))
+    }
+
+    /// Redoes the most recently undone change, given the current state of the encounter.
+    ///
+    /// Returns the description of the redone change and the state to restore.
+    pub fn redo(&mut self, current: Snapshot) -> Option<(String, Snapshot)> {
+        let step = self.redo.pop()?;
+        self.undo.push(Step { description: step.description.clone(), snapshot: current });
+        Some((step.description, step.snapshot))
+    }
+
+    /// Returns the description of the change that would be undone next.
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|step| step.description.as_str())
+    }
+
+    /// Returns the description of the change that would be redone next.
+    pub fn next_redo(&self) -> Option<&str> {
+        self.redo.last().map(|step| step.description.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(round: u32) -> Snapshot {
+        Snapshot { combatants: Vec::new(), turn: Some(0), round }
+    }
+
+    #[test]
+    fn undo_and_redo_swap_snapshots() {
+        let mut history = History::default();
+        history.record("next turn".to_string(), snapshot(1));
+        assert_eq!(history.next_undo(), Some("next turn"));
+
+        let (description, restored) = history.undo(snapshot(2)).unwrap();
+        assert_eq!((description.as_str(), restored.round), ("next turn", 1));
+        assert_eq!((history.next_undo(), history.next_redo()), (None, Some("next turn")));
+
+        let (_, restored) = history.redo(snapshot(1)).unwrap();
+        assert_eq!(restored.round, 2);
+        assert_eq!((history.next_undo(), history.next_redo()), (Some("next turn"), None));
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = History::default();
+        history.record("next turn".to_string(), snapshot(1));
+        history.undo(snapshot(2));
+        history.record("damage Goblin".to_string(), snapshot(1));
+        assert_eq!(history.next_redo(), None);
+        assert!(history.redo(snapshot(1)).is_none());
+        assert_eq!(history.next_undo(), Some("damage Goblin"));
+    }
+
+    #[test]
+    fn nothing_to_undo() {
+        let mut history = History::default();
+        assert!(history.undo(snapshot(1)).is_none());
+        assert!(history.redo(snapshot(1)).is_none());
+    }
+}
@@ -8,46 +8,115 @@
 //! The tracker uses simple Vim-like commands to navigate the interface and perform actions. If
 //! you ever get stuck in a weird state, you can always type `h` to see a list of available
 //! commands for the current context or `u` to undo the last command.
+//!
+//! Encounters can be saved to and loaded from JSON files with the `:w <file>` and `:e <file>`
+//! commands. To open an encounter on startup, pass the file as an argument:
+//!
+//! ```sh
+//! initiative-tracker goblin-ambush.json
+//! ```
+//!
+//! If the file does not exist yet, it is created the first time the encounter is saved.
+//!
+//! Monsters can be picked from a compendium of stat blocks when adding combatants. See
+//! [`compendium`] for adding your own monsters.
+//!
+//! Damage can be given a type, e.g. `2d6+3 slashing plus 1d6 fire`, and is halved, doubled or
+//! ignored according to each combatant's resistances, vulnerabilities and immunities. Everything
+//! that happened is shown in the log next to the combatant table.
+//!
+//! The encounter in progress is also autosaved every few seconds to a recovery file in
+//! `$XDG_STATE_HOME/initiative-tracker`. If the tracker exits unexpectedly, it offers to resume
+//! the encounter the next time it is launched.
 
+mod ability;
 mod actions;
 mod combatant;
+mod compendium;
+mod condition;
+mod damage;
+mod dice;
+mod history;
 mod input;
+mod recovery;
+mod resource;
 mod state;
 mod tracker;
 mod ui;
 
-use combatant::Combatant;
-use crossterm::event::{read, Event};
-use state::State;
+use combatant::{Combatant, CombatantId, CombatantKind, Downed};
+use compendium::Compendium;
+use crossterm::event::{poll, read, Event};
+use damage::{DamageInstance, DamageModifier};
+use dice::Rng;
+use history::{History, Snapshot};
+use recovery::Autosave;
+use state::{Home, Recover, State};
 use ui::Ui;
 use tracker::Tracker;
+use std::{io, path::PathBuf};
 
-fn main() -> std::io::Result<()> {
-    let mut ui = Ui::new()?;
+fn main() -> io::Result<()> {
     let mut tracker = Tracker::new();
-    let mut state = State::Home;
+    match Compendium::load() {
+        Ok(compendium) => tracker.set_compendium(compendium),
+        Err(err) => {
+            let path = Compendium::user_file().unwrap_or_default();
+            eprintln!("could not load monster compendium {}: {}", path.display(), err);
+            return Err(err);
+        },
+    }
+    if let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) {
+        match tracker.load(path.clone()) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => tracker.set_path(path),
+            Err(err) => {
+                eprintln!("could not load {}: {}", path.display(), err);
+                return Err(err);
+            },
+        }
+    }
+
+    let mut autosave = Autosave::new();
+    let mut state = match autosave.pending() {
+        Some(recovery) => State::Recover(Recover::new(recovery)),
+        None => State::Home(Home::default()),
+    };
+    state.init_tracker(&mut tracker);
 
+    let mut ui = Ui::new()?;
     loop {
-        if state == State::Quit {
+        if matches!(state, State::Quit) {
             break;
         }
 
-        ui.render(&tracker, &state)?;
+        // autosaving is best-effort; failing to write the recovery file shouldn't end the fight
+        let _ = autosave.tick(&tracker);
+
+        ui.render(&mut tracker, &state)?;
+        if let Some(timeout) = autosave.timeout(&tracker) {
+            if !poll(timeout)? {
+                continue;
+            }
+        }
         let Event::Key(event) = read()? else {
             continue;
         };
-        if state.needs_keyboard() {
-            if let Some(new_state) = state.handle_event(event, &mut tracker) {
-                state = new_state;
-                state.init_tracker(&mut tracker);
-            }
+        let new_state = if state.needs_keyboard() {
+            state.handle_event(event, &mut tracker)
         } else {
-            if let Some(transition) = state.transition(event.code) {
-                state = transition.state;
-                state.init_tracker(&mut tracker);
-            }
+            state.transition(event)
+                .map(|transition| transition.state)
+                .filter(|new_state| new_state.can_enter(&tracker))
+                .or_else(|| state.handle_event(event, &mut tracker))
+        };
+        if let Some(new_state) = new_state {
+            state = new_state;
+            state.init_tracker(&mut tracker);
         }
     }
 
-    Ok(())
+    // the program exited normally, so there is nothing to recover
+    drop(ui);
+    autosave.discard()
 }
@@ -0,0 +1,102 @@
+use crate::{history::Snapshot, tracker::Tracker};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, io, path::PathBuf, time::{Duration, Instant}};
+
+/// How often the in-progress encounter is written to the recovery file, at most.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The contents of the recovery file.
+#[derive(Serialize, Deserialize)]
+pub struct Recovery {
+    /// The file the encounter was saved to or loaded from, if any.
+    pub file: Option<PathBuf>,
+
+    /// The encounter.
+    pub encounter: Snapshot,
+}
+
+/// Periodically writes the in-progress encounter to a recovery file, so that it can be resumed if
+/// the program exits unexpectedly.
+///
+/// The recovery file is stored in `$XDG_STATE_HOME/initiative-tracker`, falling back to
+/// `~/.local/state/initiative-tracker`, and is removed when the program exits normally.
+pub struct Autosave {
+    /// The path of the recovery file, or [`None`] if no state directory could be determined.
+    path: Option<PathBuf>,
+
+    /// The revision of the encounter that was last written.
+    revision: u64,
+
+    /// When the recovery file was last written.
+    last_write: Instant,
+}
+
+impl Default for Autosave {
+    fn default() -> Autosave {
+        Autosave {
+            path: state_dir().map(|dir| dir.join("recovery.json")),
+            revision: 0,
+            last_write: Instant::now(),
+        }
+    }
+}
+
+impl Autosave {
+    /// Creates a new autosaver.
+    pub fn new() -> Autosave {
+        Autosave::default()
+    }
+
+    /// Reads the recovery file left behind by a previous run that did not exit normally.
+    pub fn pending(&self) -> Option<Recovery> {
+        let contents = fs::read_to_string(self.path.as_ref()?).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Returns how long to wait for input before the next autosave is due, or [`None`] if the
+    /// encounter has not changed since the last autosave.
+    pub fn timeout(&self, tracker: &Tracker) -> Option<Duration> {
+        (tracker.revision() != self.revision)
+            .then(|| AUTOSAVE_INTERVAL.saturating_sub(self.last_write.elapsed()))
+    }
+
+    /// Writes the encounter to the recovery file if it changed since the last write, and enough
+    /// time has passed.
+    pub fn tick(&mut self, tracker: &Tracker) -> io::Result<()> {
+        if self.timeout(tracker) != Some(Duration::ZERO) {
+            return Ok(());
+        }
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let recovery = Recovery {
+            file: tracker.path().map(PathBuf::from),
+            encounter: tracker.snapshot(),
+        };
+        fs::write(path, serde_json::to_string(&recovery)?)?;
+        self.revision = tracker.revision();
+        self.last_write = Instant::now();
+        Ok(())
+    }
+
+    /// Removes the recovery file.
+    pub fn discard(&self) -> io::Result<()> {
+        match self.path.as_ref().map(fs::remove_file) {
+            Some(Err(err)) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the directory in which to store the recovery file.
+fn state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("initiative-tracker"))
+}
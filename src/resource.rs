@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The kind of a limited-use resource, which determines how it is written and displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResourceKind {
+    /// Spell slots of the given level, from 1 to 9.
+    SpellSlot(u8),
+
+    /// Warlock pact magic slots, which are all of the given level.
+    PactSlot(u8),
+
+    /// A named ability that can be used a number of times per day, such as "Fireball 3/day".
+    PerDay,
+
+    /// A monk's ki points.
+    Ki,
+
+    /// A sorcerer's sorcery points.
+    SorceryPoints,
+
+    /// A legendary creature's uses of Legendary Resistance.
+    LegendaryResistance,
+
+    /// A named ability that is spent when used, and recharges at the start of the creature's turn
+    /// on a d6 roll of at least the given number, such as "Fire Breath (Recharge 5–6)".
+    Recharge(u8),
+}
+
+/// Returns the ordinal of a spell level, e.g. "3rd".
+fn ordinal(level: u8) -> String {
+    let suffix = match level {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{}{}", level, suffix)
+}
+
+/// A pool of limited uses of something, such as spell slots of one level or ki points.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resource {
+    /// The kind of resource.
+    pub kind: ResourceKind,
+
+    /// The name of the ability, for abilities used a number of times per day.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// The number of uses left.
+    pub current: u32,
+
+    /// The number of uses when fully restored.
+    pub max: u32,
+}
+
+impl Resource {
+    /// Creates a fully restored resource.
+    pub fn new(kind: ResourceKind, name: impl Into<String>, max: u32) -> Resource {
+        Resource { kind, name: name.into(), current: max, max }
+    }
+
+    /// Returns true if both resources are the same pool, ignoring how many uses they have.
+    pub fn same_pool(&self, other: &Resource) -> bool {
+        self.kind == other.kind && self.name == other.name
+    }
+
+    /// Returns a short label for the resource, as shown in the combatant table.
+    pub fn label(&self) -> String {
+        match self.kind {
+            ResourceKind::SpellSlot(level) => ordinal(level),
+            ResourceKind::PactSlot(level) => format!("Pact {}", ordinal(level)),
+            ResourceKind::PerDay => self.name.clone(),
+            ResourceKind::Ki => "Ki".to_string(),
+            ResourceKind::SorceryPoints => "Sorcery".to_string(),
+            ResourceKind::LegendaryResistance => "Leg. Res.".to_string(),
+            ResourceKind::Recharge(_) => self.name.clone(),
+        }
+    }
+
+    /// Returns the lowest d6 roll that recharges the ability, or [`None`] if the resource doesn't
+    /// recharge.
+    pub fn recharge(&self) -> Option<u8> {
+        match self.kind {
+            ResourceKind::Recharge(min) => Some(min),
+            _ => None,
+        }
+    }
+
+    /// Returns a description of a single use of the resource, e.g. "a 3rd level spell slot".
+    pub fn use_description(&self) -> String {
+        match self.kind {
+            ResourceKind::SpellSlot(level) => format!("a {} level spell slot", ordinal(level)),
+            ResourceKind::PactSlot(level) => format!("a {} level pact slot", ordinal(level)),
+            ResourceKind::PerDay => format!("a use of {}", self.name),
+            ResourceKind::Ki => "a ki point".to_string(),
+            ResourceKind::SorceryPoints => "a sorcery point".to_string(),
+            ResourceKind::LegendaryResistance => "a Legendary Resistance".to_string(),
+            ResourceKind::Recharge(_) => self.name.clone(),
+        }
+    }
+
+    /// Parses a list of resources separated by commas, e.g. "slots 4 3 2, pact 2x3, ki 5,
+    /// sorcery 4, legendary resistance 3, fireball 3/day, fire breath recharge 5-6".
+    pub fn parse_list(list: &str) -> Result<Vec<Resource>, String> {
+        let mut resources = Vec::new();
+        for item in list.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let number = |value: &str| value.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid number \"{}\" in \"{}\"", value.trim(), item));
+            let lower = item.to_ascii_lowercase();
+            let (word, rest) = lower.split_once(char::is_whitespace).unwrap_or((&lower, ""));
+            match word {
+                "slots" => {
+                    let slots = rest.split_whitespace().map(number).collect::<Result<Vec<_>, _>>()?;
+                    if slots.is_empty() || slots.len() > 9 {
+                        return Err("expected the spell slots of each level, e.g. slots 4 3 2".to_string());
+                    }
+                    resources.extend(
+                        (1..).zip(slots)
+                            .filter(|&(_, max)| max > 0)
+                            .map(|(level, max)| Resource::new(ResourceKind::SpellSlot(level), "", max))
+                    );
+                },
+                "pact" => {
+                    let (count, level) = rest.trim()
+                        .split_once('x')
+                        .ok_or_else(|| "expected the number of pact slots and their level, e.g. pact 2x3".to_string())?;
+                    let level = number(level).ok().and_then(|level| u8::try_from(level).ok()).filter(|level| (1..=9).contains(level))
+                        .ok_or_else(|| "pact slot level must be from 1 to 9".to_string())?;
+                    resources.push(Resource::new(ResourceKind::PactSlot(level), "", number(count)?));
+                },
+                "ki" => resources.push(Resource::new(ResourceKind::Ki, "", number(rest)?)),
+                "sorcery" => {
+                    let rest = rest.trim_start_matches("points").trim();
+                    resources.push(Resource::new(ResourceKind::SorceryPoints, "", number(rest)?));
+                },
+                "legendary" => {
+                    let rest = rest.trim_start_matches("resistances").trim_start_matches("resistance").trim();
+                    resources.push(Resource::new(ResourceKind::LegendaryResistance, "", number(rest)?));
+                },
+                _ if lower.contains(" recharge ") => {
+                    // keep the name as entered, rather than lowercased
+                    let (name, range) = item.split_at(lower.find(" recharge ").unwrap_or_default());
+                    let min = parse_recharge(&range[" recharge ".len()..])
+                        .ok_or_else(|| format!("expected a recharge range, e.g. recharge 5-6, in \"{}\"", item))?;
+                    resources.push(Resource::new(ResourceKind::Recharge(min), name.trim(), 1));
+                },
+                _ => {
+                    let (name, uses) = item.rsplit_once(char::is_whitespace)
+                        .filter(|(_, uses)| uses.to_ascii_lowercase().ends_with("/day"))
+                        .ok_or_else(|| format!("unknown resource \"{}\"", item))?;
+                    let uses = number(&uses[..uses.len() - "/day".len()])?;
+                    resources.push(Resource::new(ResourceKind::PerDay, name.trim(), uses));
+                },
+            }
+        }
+        Ok(resources)
+    }
+}
+
+/// Parses the range of d6 rolls that recharge an ability, such as "5-6", "5–6" or "6", returning
+/// the lowest roll in the range.
+pub fn parse_recharge(range: &str) -> Option<u8> {
+    let min = match range.trim().split_once(['-', '–']) {
+        Some((min, max)) if max.trim() == "6" => min.trim().parse().ok()?,
+        Some(_) => return None,
+        None => range.trim().parse().ok()?,
+    };
+    (1..=6).contains(&min).then_some(min)
+}
+
+/// Formats the range of d6 rolls that recharge an ability, e.g. "5–6".
+pub fn format_recharge(min: u8) -> String {
+    match min {
+        6 => "6".to_string(),
+        _ => format!("{}–6", min),
+    }
+}
+
+/// Formats a list of resources as entered in the form, e.g. "slots 4 3 2, ki 5".
+pub fn format_list(resources: &[Resource]) -> String {
+    let mut items = Vec::new();
+    let slots = resources.iter()
+        .filter_map(|resource| match resource.kind {
+            ResourceKind::SpellSlot(level) => Some((level, resource.max)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if let Some(highest) = slots.iter().map(|&(level, _)| level).max() {
+        let maxes = (1..=highest)
+            .map(|level| slots.iter().find(|&&(l, _)| l == level).map_or(0, |&(_, max)| max).to_string())
+            .collect::<Vec<_>>();
+        items.push(format!("slots {}", maxes.join(" ")));
+    }
+    for resource in resources {
+        match resource.kind {
+            ResourceKind::SpellSlot(_) => (),
+            ResourceKind::PactSlot(level) => items.push(format!("pact {}x{}", resource.max, level)),
+            ResourceKind::PerDay => items.push(format!("{} {}/day", resource.name, resource.max)),
+            ResourceKind::Ki => items.push(format!("ki {}", resource.max)),
+            ResourceKind::SorceryPoints => items.push(format!("sorcery {}", resource.max)),
+            ResourceKind::LegendaryResistance => items.push(format!("legendary resistance {}", resource.max)),
+            ResourceKind::Recharge(6) => items.push(format!("{} recharge 6", resource.name)),
+            ResourceKind::Recharge(min) => items.push(format!("{} recharge {}-6", resource.name, min)),
+        }
+    }
+    items.join(", ")
+}
+
+/// Formats the uses left of each resource compactly, e.g. "1st 3/4 · 2nd 1/3 · Ki 2/5".
+pub fn summary(resources: &[Resource]) -> String {
+    resources.iter()
+        .map(|resource| resource.to_string())
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Shows the resource's label and uses left, e.g. "Ki 2/5", or whether an ability that recharges
+/// is spent, e.g. "Fire Breath (spent)".
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ResourceKind::Recharge(_) if self.current == 0 => write!(f, "{} (spent)", self.label()),
+            ResourceKind::Recharge(_) => write!(f, "{} (ready)", self.label()),
+            _ => write!(f, "{} {}/{}", self.label(), self.current, self.max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_round_trip() {
+        let list = "slots 4 0 2, pact 2x3, ki 5, sorcery 4, legendary resistance 3, Misty Step 1/day, Fire Breath recharge 5-6";
+        let resources = Resource::parse_list(list).unwrap();
+        assert_eq!(resources, [
+            Resource::new(ResourceKind::SpellSlot(1), "", 4),
+            Resource::new(ResourceKind::SpellSlot(3), "", 2),
+            Resource::new(ResourceKind::PactSlot(3), "", 2),
+            Resource::new(ResourceKind::Ki, "", 5),
+            Resource::new(ResourceKind::SorceryPoints, "", 4),
+            Resource::new(ResourceKind::LegendaryResistance, "", 3),
+            Resource::new(ResourceKind::PerDay, "Misty Step", 1),
+            Resource::new(ResourceKind::Recharge(5), "Fire Breath", 1),
+        ]);
+        assert_eq!(format_list(&resources), list);
+        assert_eq!(Resource::parse_list(&format_list(&resources)).unwrap(), resources);
+    }
+
+    #[test]
+    fn lists_accept_longer_names() {
+        let resources = Resource::parse_list("Sorcery Points 3, Legendary Resistances 2, Bite recharge 6,").unwrap();
+        assert_eq!(format_list(&resources), "sorcery 3, legendary resistance 2, Bite recharge 6");
+        assert_eq!(Resource::parse_list(" ").unwrap(), []);
+    }
+
+    #[test]
+    fn invalid_lists_are_rejected() {
+        for list in ["slots", "slots 1 1 1 1 1 1 1 1 1 1", "slots 4 x", "pact 2", "pact 2x10", "ki", "fireball", "fire breath recharge 7"] {
+            assert!(Resource::parse_list(list).is_err(), "{}", list);
+        }
+    }
+
+    #[test]
+    fn recharge_ranges() {
+        assert_eq!(parse_recharge("5-6"), Some(5));
+        assert_eq!(parse_recharge("4–6"), Some(4));
+        assert_eq!(parse_recharge("6"), Some(6));
+        assert_eq!(parse_recharge("5-5"), None);
+        assert_eq!(parse_recharge("0"), None);
+        assert_eq!(format_recharge(5), "5–6");
+    }
+}
@@ -1,55 +1,445 @@
-use crate::{combatant::Combatant, input::Input, state::State, tracker::Tracker};
+use crate::{
+    ability::Ability,
+    actions::{self, Action, Actions},
+    combatant::{Combatant, CombatantKind},
+    compendium::StatBlock,
+    damage::{self, DamageEntry},
+    dice::{Expression, Rng, Roll, RolledTerm},
+    input::Input,
+    resource::{self, Resource},
+    state::{Home, State},
+    tracker::Tracker,
+};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
+use std::collections::BTreeMap;
 
-/// Adding a new combatant to the initiative order.
+/// A field of the [`AddCombatant`] form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    /// The monster from the compendium whose stat block the combatant uses.
+    Monster,
+
+    /// Whether the hit points of the monster are rolled from its hit dice instead of using its
+    /// average hit points.
+    RollHitPoints,
+
+    /// The name of the combatant.
+    Name,
+
+    /// The number of identical combatants to add.
+    Count,
+
+    /// Whether a group of combatants is suffixed with letters instead of numbers.
+    LetterSuffixes,
+
+    /// The current hit points of the combatant.
+    HitPoints,
+
+    /// The maximum hit points of the combatant, which are the same as its hit points if left
+    /// empty.
+    MaxHitPoints,
+
+    /// The temporary hit points of the combatant.
+    TempHitPoints,
+
+    /// The initiative of the combatant.
+    Initiative,
+
+    /// Whether a group of combatants shares a single initiative roll.
+    SharedInitiative,
+
+    /// The modifier added to the combatant's initiative rolls.
+    InitiativeModifier,
+
+    /// Whether the combatant rolls initiative with advantage.
+    InitiativeAdvantage,
+
+    /// Whether the combatant is a player character.
+    Player,
+
+    /// The Armor Class of the combatant.
+    ArmorClass,
+
+    /// The six ability scores of the combatant.
+    AbilityScores,
+
+    /// The saving throw modifiers the combatant is proficient in.
+    SavingThrows,
+
+    /// The damage types the combatant is resistant to.
+    Resistances,
+
+    /// The damage types the combatant is vulnerable to.
+    Vulnerabilities,
+
+    /// The damage types the combatant is immune to.
+    Immunities,
+
+    /// The combatant's limited-use resources, such as spell slots.
+    Resources,
+
+    /// The actions the combatant has each turn.
+    Actions,
+
+    /// The number of legendary actions the combatant can take each round.
+    LegendaryActions,
+}
+
+impl Field {
+    /// All fields, in the order they appear in the form.
+    pub const ALL: [Field; 22] = [
+        Field::Monster,
+        Field::RollHitPoints,
+        Field::Name,
+        Field::Count,
+        Field::LetterSuffixes,
+        Field::HitPoints,
+        Field::MaxHitPoints,
+        Field::TempHitPoints,
+        Field::ArmorClass,
+        Field::Initiative,
+        Field::SharedInitiative,
+        Field::InitiativeModifier,
+        Field::InitiativeAdvantage,
+        Field::Player,
+        Field::AbilityScores,
+        Field::SavingThrows,
+        Field::Resistances,
+        Field::Vulnerabilities,
+        Field::Immunities,
+        Field::Resources,
+        Field::Actions,
+        Field::LegendaryActions,
+    ];
+
+    /// Returns the label of the field.
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Monster => "Monster",
+            Field::RollHitPoints => "Roll Hit Points",
+            Field::Name => "Name",
+            Field::Count => "Count",
+            Field::LetterSuffixes => "Letter Suffixes",
+            Field::HitPoints => "Hit Points",
+            Field::MaxHitPoints => "Max Hit Points",
+            Field::TempHitPoints => "Temp Hit Points",
+            Field::Initiative => "Initiative",
+            Field::SharedInitiative => "Shared Initiative",
+            Field::InitiativeModifier => "Initiative Modifier",
+            Field::InitiativeAdvantage => "Initiative Advantage",
+            Field::Player => "Player Character",
+            Field::ArmorClass => "Armor Class",
+            Field::AbilityScores => "Str Dex Con Int Wis Cha",
+            Field::SavingThrows => "Saving Throws",
+            Field::Resistances => "Damage Resistances",
+            Field::Vulnerabilities => "Damage Vulnerabilities",
+            Field::Immunities => "Damage Immunities",
+            Field::Resources => "Resources",
+            Field::Actions => "Actions",
+            Field::LegendaryActions => "Legendary Actions",
+        }
+    }
+
+    /// Returns a description of what entering a value in the field does.
+    pub fn help(self) -> &'static str {
+        match self {
+            Field::Monster => "pick the highlighted monster, filling in its statistics (empty for none)",
+            Field::RollHitPoints => "toggle rolling the monster's hit dice instead of using its average hit points (or y / n)",
+            Field::Name => "set name",
+            Field::Count => "set number of combatants to add, numbered 1 to N",
+            Field::LetterSuffixes => "toggle suffixing names with A, B, C instead of 1, 2, 3 (or y / n)",
+            Field::HitPoints => "set hit points (number or dice, e.g. 2d6+2)",
+            Field::MaxHitPoints => "set maximum hit points (empty for same as hit points)",
+            Field::TempHitPoints => "set temporary hit points",
+            Field::Initiative => "set initiative (number or dice, e.g. 1d20+2)",
+            Field::SharedInitiative => "toggle rolling one initiative for the whole group (or y / n)",
+            Field::InitiativeModifier => "set initiative modifier (e.g. +2)",
+            Field::InitiativeAdvantage => "toggle advantage on initiative (or y / n)",
+            Field::Player => "toggle player character (or y / n)",
+            Field::ArmorClass => "set armor class (empty for 10)",
+            Field::AbilityScores => "set the six ability scores, e.g. 8 14 10 10 8 8 (empty for all 10)",
+            Field::SavingThrows => "set proficient saving throws, e.g. dex +4, wis +2",
+            Field::Resistances => "set damage resistances, e.g. cold, nonmagical slashing",
+            Field::Vulnerabilities => "set damage vulnerabilities, e.g. fire",
+            Field::Immunities => "set damage immunities, e.g. poison",
+            Field::Resources => "set limited-use resources, e.g. slots 4 3 2, pact 2x3, ki 5, sorcery 4, legendary resistance 3, fireball 3/day, fire breath recharge 5-6",
+            Field::Actions => "set the actions each turn, listing an action twice for an extra one and LA for Haste's limited action, e.g. M A LA BA R (empty for M A R)",
+            Field::LegendaryActions => "set legendary actions per round (empty for none)",
+        }
+    }
+
+    /// Returns true if the field only applies when adding combatants, not when editing one.
+    pub fn is_add_only(self) -> bool {
+        matches!(self, Field::Monster | Field::RollHitPoints | Field::Count | Field::LetterSuffixes | Field::SharedInitiative)
+    }
+
+    /// Returns true if the field is a list of damage types.
+    pub fn is_damage_list(self) -> bool {
+        matches!(self, Field::Resistances | Field::Vulnerabilities | Field::Immunities)
+    }
+
+    /// Returns true if the field is a number that can be entered as dice, rolled separately for
+    /// each combatant.
+    pub fn is_rolled(self) -> bool {
+        matches!(self, Field::HitPoints | Field::MaxHitPoints | Field::TempHitPoints | Field::Initiative)
+    }
+
+    /// Returns true if the field is a yes / no toggle.
+    pub fn is_toggle(self) -> bool {
+        matches!(self, Field::RollHitPoints | Field::LetterSuffixes | Field::SharedInitiative | Field::InitiativeAdvantage | Field::Player)
+    }
+}
+
+/// Parses a yes / no answer.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "n" | "no" => Some(false),
+        "y" | "yes" => Some(true),
+        _ => None,
+    }
+}
+
+/// Parses a signed integer, allowing a leading `+`.
+fn parse_int(value: &str) -> Option<i32> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Some(0);
+    }
+    value.strip_prefix('+').unwrap_or(value).parse().ok()
+}
+
+/// Parses the actions a combatant has each turn, which are a move, an action and a reaction if
+/// left empty.
+fn parse_actions(value: &str) -> Result<Vec<Action>, String> {
+    match value.trim() {
+        "" => Ok(Actions::default().kinds()),
+        value => actions::parse_list(value),
+    }
+}
+
+/// Parses the number of legendary actions per round, which is 0 if left empty.
+fn parse_legendary(value: &str) -> Result<u32, String> {
+    match value.trim() {
+        "" => Ok(0),
+        value => value.parse().map_err(|_| "legendary actions must be a number".to_string()),
+    }
+}
+
+/// The most combatants that can be added in one step.
+const MAX_COUNT: u32 = 50;
+
+/// The most monsters shown when searching the compendium.
+const MAX_MATCHES: usize = 5;
+
+/// Parses the number of combatants to add, which is 1 if left empty.
+fn parse_count(value: &str) -> Result<u32, String> {
+    match value.trim() {
+        "" => Ok(1),
+        value => value.parse()
+            .ok()
+            .filter(|count| (1..=MAX_COUNT).contains(count))
+            .ok_or_else(|| format!("count must be a number from 1 to {}", MAX_COUNT)),
+    }
+}
+
+/// Parses the six ability scores, separated by spaces or commas. All scores are 10 if left empty.
+fn parse_scores(value: &str) -> Result<[i32; 6], String> {
+    if value.trim().is_empty() {
+        return Ok([10; 6]);
+    }
+    let scores = value.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|score| !score.is_empty())
+        .map(|score| score.parse::<i32>().ok().filter(|score| (1..=30).contains(score)))
+        .collect::<Option<Vec<_>>>();
+    scores.and_then(|scores| scores.try_into().ok())
+        .ok_or_else(|| "expected six ability scores from 1 to 30".to_string())
+}
+
+/// Formats ability scores as entered in the form, e.g. "8 14 10 10 8 8".
+fn format_scores(scores: [i32; 6]) -> String {
+    scores.map(|score| score.to_string()).join(" ")
+}
+
+/// Parses saving throw modifiers, e.g. "dex +4, wis +2".
+fn parse_saves(value: &str) -> Result<BTreeMap<Ability, i32>, String> {
+    value.split(',')
+        .filter(|save| !save.trim().is_empty())
+        .map(|save| {
+            let (name, modifier) = save.trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("expected an ability and a modifier, e.g. dex +4, not \"{}\"", save.trim()))?;
+            let ability = Ability::parse(name).ok_or_else(|| format!("unknown ability \"{}\"", name))?;
+            let modifier = parse_int(modifier).ok_or_else(|| format!("invalid modifier \"{}\"", modifier.trim()))?;
+            Ok((ability, modifier))
+        })
+        .collect()
+}
+
+/// Formats saving throw modifiers as entered in the form, e.g. "Dex +4, Wis +2".
+fn format_saves(saves: &BTreeMap<Ability, i32>) -> String {
+    saves.iter()
+        .map(|(ability, modifier)| format!("{} {:+}", ability.abbreviation(), modifier))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Rolls the value of a field entered as a number or dice for the named combatant. A roll with
+/// dice is added to `rolls`, to be shown in the form.
+fn roll_field(field: Field, value: &str, name: &str, rng: &mut Rng, rolls: &mut Vec<(String, Roll)>) -> Result<i32, String> {
+    if value.is_empty() {
+        return Ok(0);
+    }
+    let roll = value.parse::<Expression>()
+        .map(|expression| expression.roll(rng))
+        .map_err(|error| format!("{}: {}", field.label().to_lowercase(), error))?;
+    let total = roll.total();
+    if roll.terms.iter().any(|(_, term)| matches!(term, RolledTerm::Dice { .. })) {
+        rolls.push((format!("{} {}", name, field.label().to_lowercase()), roll));
+    }
+    Ok(total)
+}
+
+/// Adding new combatants to the initiative order, or editing an existing one.
 #[derive(Default, PartialEq, Eq)]
 pub struct AddCombatant {
-    /// The name of the combatant.
-    pub name: Option<String>,
+    /// Whether the highlighted combatant is edited instead of adding a new combatant.
+    pub edit: bool,
+
+    /// The index of the combatant being edited.
+    pub editing: Option<usize>,
 
-    /// The hit points of the combatant.
-    pub hit_points: Option<i32>,
+    /// The entered value of each field, indexed by the field's discriminant (`field as usize`),
+    /// which is its position in the enum rather than in [`Field::ALL`]. Dice are kept as entered
+    /// and rolled when the combatants are added.
+    pub values: [String; Field::ALL.len()],
 
-    /// The current row being edited.
+    /// The current row being edited, indexing into [`AddCombatant::fields`].
     pub row: usize,
 
+    /// The error from the last entered value, if it was invalid.
+    pub error: Option<String>,
+
+    /// The index of the highlighted monster among those matching the search in the
+    /// [`Field::Monster`] field.
+    pub selected_match: usize,
+
+    /// The stat block of the picked monster, attached to the added combatants.
+    pub stat_block: Option<Box<StatBlock>>,
+
+    /// The dice rolled for the added or edited combatants, and what they were rolled for. Once
+    /// the combatants are added, the form shows these rolls instead of the fields until it is
+    /// closed.
+    pub rolls: Vec<(String, Roll)>,
+
     input: Input,
 }
 
 impl AddCombatant {
+    /// Creates the state for editing the highlighted combatant.
+    pub fn edit() -> AddCombatant {
+        AddCombatant { edit: true, ..Default::default() }
+    }
+
     /// Returns the [`Input`] widget.
     pub fn input(&self) -> &Input {
         &self.input
     }
 
+    /// Returns the fields shown in the form.
+    fn fields(&self) -> Vec<Field> {
+        Field::ALL.into_iter()
+            .filter(|field| !(self.editing.is_some() && field.is_add_only()))
+            .collect()
+    }
+
+    /// Returns the field currently being edited.
+    fn field(&self) -> Field {
+        self.fields()[self.row]
+    }
+
     pub fn help(&self) -> String {
-        format!(
-            "<escape>: cancel, back to initiative tracker\n<enter>: {0}\n<ctrl-enter>: {0} and finish",
-            match self.row {
-                0 => "set name",
-                1 => "set hit points",
-                _ => "",
-            }
-        )
+        if !self.rolls.is_empty() {
+            return "<escape>: back to initiative tracker".to_string();
+        }
+        let mut help = format!(
+            "<escape>: cancel, back to initiative tracker\n<enter>: {0}\n<ctrl-enter>: {0} and finish\n<up>: previous field\n<down>: next field",
+            self.field().help(),
+        );
+        if self.field() == Field::Monster {
+            help.push_str("\n<tab>: highlight next monster\n<shift-tab>: highlight previous monster");
+        }
+        help
     }
 
-    pub fn render(&self) -> Table {
-        fn highlight<'a>(name: &'a str, value: &'a str) -> Row<'a> {
-            Row::new([Text::from(name), Text::from(value)])
-                .style(Style::default().bg(Color::Rgb(0, 48, 130)))
+    /// Returns the monsters matching the search in the [`Field::Monster`] field, if it is being
+    /// edited.
+    fn matches<'a>(&self, tracker: &'a Tracker) -> Vec<&'a StatBlock> {
+        if self.field() != Field::Monster || self.input.is_empty() {
+            return Vec::new();
         }
+        let mut matches = tracker.compendium().search(self.input.as_str());
+        matches.truncate(MAX_MATCHES);
+        matches
+    }
 
-        let maybe_highlight = |do_highlight, name, value| if do_highlight {
-            highlight(name, self.input.as_str())
-        } else {
-            Row::new([Text::from(name), Text::from(value)])
-        };
+    /// Renders the form, scrolled so that the current row and the highlighted monster are
+    /// visible.
+    pub fn render(&self, tracker: &Tracker) -> (Table<'_>, TableState) {
+        if !self.rolls.is_empty() {
+            return (self.render_rolls(), TableState::default());
+        }
+
+        let matches = self.matches(tracker)
+            .into_iter()
+            .enumerate()
+            .map(|(i, monster)| {
+                let row = Row::new([
+                    Text::from(""),
+                    Text::from(format!("{} (CR {})", monster.name, monster.challenge_rating)),
+                ]);
+                if i == self.selected_match {
+                    row.bold()
+                } else {
+                    row.dark_gray()
+                }
+            })
+            .collect::<Vec<_>>();
+        let selected = self.row + matches.len().min(self.selected_match + 1);
+        let error = self.error.as_ref()
+            .map(|error| Row::new([Text::from("Error"), Text::from(error.as_str())]).red());
+
+        let mut rows = Vec::new();
+        for (i, field) in self.fields().into_iter().enumerate() {
+            if i == self.row {
+                rows.push(
+                    Row::new([Text::from(field.label()), Text::from(self.input.as_str())])
+                        .style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                );
+                rows.extend(matches.iter().cloned());
+                rows.extend(error.iter().cloned());
+            } else {
+                rows.push(Row::new([Text::from(field.label()), Text::from(self.value(field).to_string())]));
+            }
+        }
+
+        let table = Table::new(rows, [Constraint::Percentage(50), Constraint::Percentage(50)])
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(if self.editing.is_some() { "Edit Combatant" } else { "Add Combatant" })
+            );
+        (table, TableState::default().with_selected(Some(selected)))
+    }
+
+    /// Renders the dice rolled for the combatants once they are added.
+    fn render_rolls(&self) -> Table<'_> {
         Table::new(
-            [
-                maybe_highlight(self.row == 0, "Name", self.name.as_ref().map(|name| name.to_string()).unwrap_or_default()),
-                maybe_highlight(self.row == 1, "Hit Points", self.hit_points.as_ref().map(|hp| hp.to_string()).unwrap_or_default()),
-            ],
+            self.rolls.iter().map(|(label, roll)| {
+                Row::new([Text::from(label.as_str()).bold(), Text::from(roll.to_string())])
+            }),
             [Constraint::Percentage(50), Constraint::Percentage(50)],
         )
             .block(
@@ -57,58 +447,345 @@ impl AddCombatant {
                     .border_type(BorderType::Rounded)
                     .border_style(Style::default().fg(Color::White))
                     .padding(Padding::horizontal(1))
-                    .title("Add Combatant")
+                    .title(if self.editing.is_some() {
+                        "Combatant edited, <escape> to finish"
+                    } else {
+                        "Combatants added, <escape> to finish"
+                    })
             )
     }
 
+    /// Fills the form with the values of the combatant being edited, if any.
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        if !self.edit {
+            return;
+        }
+        self.editing = tracker.highlighted().filter(|&idx| idx < tracker.combatants().len());
+        let Some(combatant) = self.editing.and_then(|idx| tracker.combatant(idx)) else {
+            return;
+        };
+
+        let yes_no = |value: bool| if value { "y" } else { "n" }.to_string();
+        for field in self.fields() {
+            *self.value_mut(field) = match field {
+                Field::Name => combatant.name().to_string(),
+                Field::HitPoints => combatant.hit_points().to_string(),
+                Field::MaxHitPoints => combatant.max_hit_points().to_string(),
+                Field::TempHitPoints => combatant.temp_hit_points().to_string(),
+                Field::Initiative => combatant.initiative().to_string(),
+                Field::InitiativeModifier => format!("{:+}", combatant.initiative_modifier),
+                Field::InitiativeAdvantage => yes_no(combatant.initiative_advantage),
+                Field::Player => yes_no(combatant.kind == CombatantKind::Player),
+                Field::ArmorClass => combatant.armor_class.to_string(),
+                Field::AbilityScores => format_scores(combatant.ability_scores),
+                Field::SavingThrows => format_saves(&combatant.saving_throws),
+                Field::Resistances => damage::format_list(&combatant.resistances),
+                Field::Vulnerabilities => damage::format_list(&combatant.vulnerabilities),
+                Field::Immunities => damage::format_list(&combatant.immunities),
+                Field::Resources => resource::format_list(&combatant.resources),
+                Field::Actions => actions::format_list(&combatant.action_kinds()),
+                Field::LegendaryActions => combatant.legendary_actions()
+                    .map(|legendary| legendary.per_round.to_string())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+        }
+        self.input.set(self.value(self.field()).to_string());
+    }
+
+    /// Moves to the given row, keeping whatever was entered in the current row.
     pub fn set_row_idx(&mut self, row: usize) {
+        *self.value_mut(self.field()) = self.input.take();
         self.row = row;
-        match self.row {
-            0 => self.input.set(self.name.take().unwrap_or_default()),
-            1 => self.input.set(self.hit_points.take().map(|hp| hp.to_string()).unwrap_or_default()),
-            _ => (),
+        self.input.set(self.value(self.field()).to_string());
+    }
+
+    /// Sets the content of the current row.
+    ///
+    /// Returns false if the content was invalid, in which case it stays in the input.
+    pub fn set_row_content(&mut self, tracker: &Tracker) -> bool {
+        let content = self.input.as_str().trim().to_string();
+        let value = match self.field() {
+            Field::HitPoints | Field::MaxHitPoints if parse_int(&content).is_some_and(|hp| hp < 0) => {
+                Err("hit points can't be negative".to_string())
+            },
+            field if field.is_rolled() && !content.is_empty() => content.parse::<Expression>()
+                .map(|_| content)
+                .map_err(|error| error.to_string()),
+            Field::Monster => return self.pick_monster(tracker),
+            Field::Count => parse_count(&content).map(|_| content),
+            Field::ArmorClass => parse_int(&content)
+                .map(|_| content)
+                .ok_or_else(|| "armor class must be a number".to_string()),
+            Field::AbilityScores => parse_scores(&content).map(format_scores),
+            Field::SavingThrows => parse_saves(&content).map(|saves| format_saves(&saves)),
+            field if field.is_damage_list() => DamageEntry::parse_list(&content).map(|entries| damage::format_list(&entries)),
+            Field::Resources => Resource::parse_list(&content).map(|resources| resource::format_list(&resources)),
+            Field::Actions => parse_actions(&content).map(|kinds| actions::format_list(&kinds)),
+            Field::LegendaryActions => parse_legendary(&content).map(|_| content),
+            Field::InitiativeModifier => parse_int(&content)
+                .map(|_| content)
+                .ok_or_else(|| "initiative modifier must be a number".to_string()),
+            field if field.is_toggle() => {
+                // entering the value that is already set toggles it
+                let current = parse_bool(self.value(field)) == Some(true);
+                match parse_bool(&content) {
+                    Some(value) if content.is_empty() || value == current => Ok(!current),
+                    Some(value) => Ok(value),
+                    None => Err("expected y or n".to_string()),
+                }
+                    .map(|value| if value { "y" } else { "n" }.to_string())
+            },
+            _ => Ok(content),
+        };
+
+        match value {
+            Ok(value) => {
+                if self.field() == Field::RollHitPoints {
+                    *self.value_mut(Field::RollHitPoints) = value.clone();
+                    self.fill_hit_points();
+                }
+                self.input.set(value);
+                self.error = None;
+                true
+            },
+            Err(error) => {
+                self.error = Some(error);
+                false
+            },
         }
     }
 
-    pub fn set_row_content(&mut self, content: String) {
-        match self.row {
-            0 => self.name = Some(content),
-            1 => self.hit_points = Some(content.parse().unwrap_or_default()),
-            _ => (),
+    /// Attaches the highlighted monster matching the search to the combatant, filling in the form
+    /// from its stat block. If the search is empty, the combatant will not have a stat block.
+    ///
+    /// Returns false if no monster matches the search.
+    fn pick_monster(&mut self, tracker: &Tracker) -> bool {
+        if self.input.is_empty() {
+            self.stat_block = None;
+            self.error = None;
+            return true;
         }
+        let Some(monster) = self.matches(tracker).get(self.selected_match).map(|&m| Box::new(m.clone())) else {
+            self.error = Some(format!("no monster matches \"{}\"", self.input.as_str()));
+            return false;
+        };
+
+        self.input.set(monster.name.clone());
+        *self.value_mut(Field::Name) = monster.name.clone();
+        *self.value_mut(Field::MaxHitPoints) = String::new();
+        *self.value_mut(Field::InitiativeModifier) = format!("{:+}", monster.initiative_modifier());
+        *self.value_mut(Field::ArmorClass) = monster.armor_class.to_string();
+        *self.value_mut(Field::AbilityScores) = format_scores(Ability::ALL.map(|ability| monster.score(ability)));
+        *self.value_mut(Field::SavingThrows) = format_saves(&monster.saving_throws);
+        // damage types the tracker doesn't understand are left out rather than refusing the monster
+        let damage_list = |list: &str| damage::format_list(&DamageEntry::parse_known(list).0);
+        *self.value_mut(Field::Resistances) = damage_list(&monster.damage_resistances);
+        *self.value_mut(Field::Vulnerabilities) = damage_list(&monster.damage_vulnerabilities);
+        *self.value_mut(Field::Immunities) = damage_list(&monster.damage_immunities);
+        *self.value_mut(Field::Resources) = resource::format_list(&monster.resources());
+        *self.value_mut(Field::LegendaryActions) = match monster.legendary_actions_per_round() {
+            0 => String::new(),
+            per_round => per_round.to_string(),
+        };
+        self.stat_block = Some(monster);
+        self.fill_hit_points();
+        self.selected_match = 0;
+        self.error = None;
+        true
+    }
+
+    /// Fills in the hit points from the picked monster's stat block, either its average hit
+    /// points or its hit dice to roll for each combatant.
+    fn fill_hit_points(&mut self) {
+        let Some(monster) = &self.stat_block else {
+            return;
+        };
+        let roll = parse_bool(self.value(Field::RollHitPoints)) == Some(true);
+        *self.value_mut(Field::HitPoints) = if roll && !monster.hit_dice.is_empty() {
+            monster.hit_dice.clone()
+        } else {
+            monster.hit_points.to_string()
+        };
+    }
+
+    /// Returns the entered value of the given field.
+    fn value(&self, field: Field) -> &str {
+        self.values[field as usize].trim()
+    }
+
+    /// Returns the entered value of the given field, to replace it.
+    fn value_mut(&mut self, field: Field) -> &mut String {
+        &mut self.values[field as usize]
+    }
+
+    /// Builds a combatant from the entered values, starting from the given combatant when
+    /// editing. Fields entered as dice are rolled for this combatant alone, except for the
+    /// initiative, which is rolled by the caller so that it can be shared. Rolls with dice are
+    /// added to `rolls`.
+    fn combatant(
+        &self,
+        name: String,
+        initiative: Option<i32>,
+        base: Option<Combatant>,
+        rng: &mut Rng,
+        rolls: &mut Vec<(String, Roll)>,
+    ) -> Result<Combatant, String> {
+        let mut number = |field: Field, rng: &mut Rng| roll_field(field, self.value(field), &name, rng, rolls);
+
+        let hit_points = number(Field::HitPoints, rng)?;
+        let max_hit_points = match self.value(Field::MaxHitPoints) {
+            "" => hit_points,
+            _ => number(Field::MaxHitPoints, rng)?,
+        };
+        let temp_hit_points = number(Field::TempHitPoints, rng)?.max(0);
+        if hit_points < 0 || max_hit_points < 0 {
+            return Err(format!("{} can't have negative hit points", name));
+        }
+        if hit_points > max_hit_points {
+            return Err(format!("{} has more hit points ({}) than their maximum ({})", name, hit_points, max_hit_points));
+        }
+
+        let mut combatant = base.unwrap_or_else(|| {
+            let mut combatant = Combatant::new(String::new(), hit_points, max_hit_points);
+            combatant.stat_block = self.stat_block.clone();
+            combatant
+        });
+        combatant.set_name(name);
+        if let Some(initiative) = initiative.filter(|&initiative| initiative != combatant.initiative()) {
+            combatant.set_initiative(initiative);
+        }
+        combatant.set_hit_points(hit_points, max_hit_points, temp_hit_points);
+        combatant.initiative_modifier = parse_int(self.value(Field::InitiativeModifier))
+            .ok_or_else(|| "initiative modifier must be a number".to_string())?;
+        combatant.initiative_advantage = parse_bool(self.value(Field::InitiativeAdvantage)) == Some(true);
+        combatant.armor_class = match self.value(Field::ArmorClass) {
+            "" => 10,
+            value => parse_int(value).ok_or_else(|| "armor class must be a number".to_string())?,
+        };
+        combatant.ability_scores = parse_scores(self.value(Field::AbilityScores))?;
+        combatant.saving_throws = parse_saves(self.value(Field::SavingThrows))?;
+        combatant.resistances = DamageEntry::parse_list(self.value(Field::Resistances))?;
+        combatant.vulnerabilities = DamageEntry::parse_list(self.value(Field::Vulnerabilities))?;
+        combatant.immunities = DamageEntry::parse_list(self.value(Field::Immunities))?;
+        // an edited combatant keeps the uses they have already spent
+        let mut resources = Resource::parse_list(self.value(Field::Resources))?;
+        for resource in &mut resources {
+            if let Some(old) = combatant.resources.iter().find(|old| old.same_pool(resource)) {
+                resource.current = old.current.min(resource.max);
+            }
+        }
+        combatant.resources = resources;
+        combatant.set_action_kinds(&parse_actions(self.value(Field::Actions))?);
+        combatant.set_legendary_actions(parse_legendary(self.value(Field::LegendaryActions))?);
+        combatant.kind = if parse_bool(self.value(Field::Player)) == Some(true) {
+            CombatantKind::Player
+        } else if combatant.kind == CombatantKind::Lair {
+            CombatantKind::Lair
+        } else {
+            CombatantKind::Monster
+        };
+        Ok(combatant)
+    }
+
+    /// Adds the combatants, or replaces the edited combatant, in the tracker, returning the dice
+    /// rolled for them.
+    fn submit(&self, tracker: &mut Tracker) -> Result<Vec<(String, Roll)>, String> {
+        let name = self.value(Field::Name).to_string();
+        let initiative = |name: &str, rng: &mut Rng, rolls: &mut Vec<(String, Roll)>| match self.value(Field::Initiative) {
+            "" => Ok(None),
+            value => roll_field(Field::Initiative, value, name, rng, rolls).map(Some),
+        };
+        let mut rolls = Vec::new();
+
+        if let Some(idx) = self.editing {
+            let base = tracker.combatant(idx).cloned();
+            let initiative = initiative(&name, tracker.rng(), &mut rolls)?;
+            let combatant = self.combatant(name, initiative, base, tracker.rng(), &mut rolls)?;
+            tracker.replace_combatant(idx, combatant);
+            return Ok(rolls);
+        }
+
+        let count = parse_count(self.value(Field::Count))?;
+        let shared = parse_bool(self.value(Field::SharedInitiative)) == Some(true);
+        // a shared roll is shown under the name of the whole group
+        let shared_initiative = match shared {
+            true => initiative(&name, tracker.rng(), &mut rolls)?,
+            false => None,
+        };
+        let names = match count {
+            1 => vec![name],
+            _ => tracker.group_names(&name, count, parse_bool(self.value(Field::LetterSuffixes)) == Some(true)),
+        };
+        let combatants = names.into_iter()
+            .map(|name| {
+                let initiative = match shared {
+                    true => shared_initiative,
+                    false => initiative(&name, tracker.rng(), &mut rolls)?,
+                };
+                self.combatant(name, initiative, None, tracker.rng(), &mut rolls)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        tracker.add_group(combatants, shared);
+        Ok(rolls)
     }
 
     pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        // the combatants were added, and only the rolls are shown
+        if !self.rolls.is_empty() {
+            return (key.code == KeyCode::Esc).then(|| State::Home(Home::default()));
+        }
+
         let Some(unhandled_key) = self.input.update(key) else {
+            // the search changed, so highlight the best match
+            self.selected_match = 0;
             return None;
         };
         match unhandled_key.code {
-            KeyCode::Esc => Some(State::Home),
+            KeyCode::Tab if self.field() == Field::Monster => {
+                let len = self.matches(tracker).len().max(1);
+                self.selected_match = (self.selected_match + 1) % len;
+                None
+            },
+            KeyCode::BackTab if self.field() == Field::Monster => {
+                let len = self.matches(tracker).len().max(1);
+                self.selected_match = (self.selected_match + len - 1) % len;
+                None
+            },
+            KeyCode::Esc => Some(State::Home(Home::default())),
             KeyCode::Enter => {
-                let content = self.input.take();
-                self.set_row_content(content);
-                self.set_row_idx((self.row + 1) % 2);
+                if self.set_row_content(tracker) {
+                    self.set_row_idx((self.row + 1) % self.fields().len());
+                }
                 None
             },
             KeyCode::Down => {
-                self.set_row_idx((self.row + 1) % 2);
+                self.set_row_idx((self.row + 1) % self.fields().len());
                 None
             },
             KeyCode::Up => {
-                self.set_row_idx((self.row + 1) % 2);
+                let len = self.fields().len();
+                self.set_row_idx((self.row + len - 1) % len);
                 None
             },
             // enter seems to return ctrl-j
             KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                let content = self.input.take();
-                self.set_row_content(content);
-                let hp = self.hit_points.take().unwrap_or_default();
-                tracker.add_combatant(Combatant::new(
-                    self.name.take().unwrap_or_default(),
-                    hp,
-                    hp,
-                ));
-                Some(State::Home)
+                if !self.set_row_content(tracker) {
+                    return None;
+                }
+                *self.value_mut(self.field()) = self.input.take();
+                match self.submit(tracker) {
+                    Ok(rolls) if rolls.is_empty() => Some(State::Home(Home::default())),
+                    Ok(rolls) => {
+                        self.rolls = rolls;
+                        None
+                    },
+                    Err(error) => {
+                        self.input.set(self.value(self.field()).to_string());
+                        self.error = Some(error);
+                        None
+                    },
+                }
             },
             _ => None,
         }
@@ -0,0 +1,289 @@
+use crate::{
+    actions::Action,
+    compendium::Attack as StatBlockAttack,
+    damage::{self, TypedDamage},
+    dice::{Expression, RollMode},
+    input::Input,
+    state::{combatant::check::ConcentrationCheck, Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+
+/// Parses an attack entered as `<bonus> <damage>`, e.g. `+5 1d12+3 slashing`.
+fn parse_attack(input: &str) -> Result<(i32, Vec<TypedDamage>), String> {
+    let (bonus, damage) = input.trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| "expected an attack bonus and damage, e.g. +5 1d12+3 slashing".to_string())?;
+    let bonus = bonus.strip_prefix('+').unwrap_or(bonus)
+        .parse()
+        .map_err(|_| format!("invalid attack bonus \"{}\"", bonus))?;
+    let damage = TypedDamage::parse_all(damage)?;
+    Ok((bonus, damage))
+}
+
+/// Resolving an attack roll from one combatant against another's Armor Class, and applying the
+/// damage on a hit.
+#[derive(Default, PartialEq, Eq)]
+pub struct Attack {
+    /// The index of the attacking combatant.
+    pub attacker: usize,
+
+    /// The index of the targeted combatant.
+    pub row: usize,
+
+    /// Whether the attack is rolled with advantage or disadvantage.
+    pub mode: RollMode,
+
+    /// The attacks from the attacker's stat block, which can be picked instead of typing one.
+    attacks: Vec<StatBlockAttack>,
+
+    /// The index of the last picked attack from the attacker's stat block.
+    pub picked: Option<usize>,
+
+    /// The name, Armor Class and hit points of each combatant, for display.
+    rows: Vec<(String, String, String)>,
+
+    /// The rolls and outcome of the last attack.
+    pub last_attack: Vec<String>,
+
+    /// The error from parsing the last entered attack, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl Attack {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        let mut help = "<escape>: back to initiative tracker\n<enter>: roll attack against target, entered as attack bonus and damage (e.g. +5 1d12+3 slashing)\n<shift-tab>: cycle advantage / disadvantage\n<up>: previous target\n<down>: next target\n<ctrl-p> / <ctrl-n>: previous / next attacker".to_string();
+        if !self.attacks.is_empty() {
+            help.push_str("\n<tab>: pick next attack from the attacker's stat block");
+        }
+        help
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let attacker = self.rows.get(self.attacker).map(|(name, _, _)| name.as_str()).unwrap_or_default();
+        let title = match (self.picked.and_then(|idx| self.attacks.get(idx)), self.mode) {
+            (Some(attack), RollMode::Normal) => format!("{} attacks with {}", attacker, attack.name),
+            (Some(attack), mode) => format!("{} attacks with {} ({})", attacker, attack.name, mode.label()),
+            (None, RollMode::Normal) => format!("{} attacks", attacker),
+            (None, mode) => format!("{} attacks ({})", attacker, mode.label()),
+        };
+        let last = match &self.error {
+            Some(error) => vec![Row::new([Text::from("Error"), Text::from(error.as_str()), Text::from("")]).red()],
+            None => self.last_attack.iter()
+                .enumerate()
+                .map(|(i, line)| Row::new([Text::from(if i == 0 { "Last" } else { "" }), Text::from(line.as_str()), Text::from("")]))
+                .collect(),
+        };
+        Table::new(
+            self.rows.iter()
+                .enumerate()
+                .map(|(i, (name, ac, hp))| {
+                    let marker = if i == self.attacker { "attacker" } else { "" };
+                    let row = Row::new([Text::from(marker), Text::from(format!("{} {}", name, hp)), Text::from(ac.as_str())]);
+                    if i == self.row {
+                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                    } else {
+                        row
+                    }
+                })
+                .chain(last),
+            [Constraint::Length(10), Constraint::Fill(1), Constraint::Length(8)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(title)
+            )
+    }
+
+    pub fn set_row(&mut self, row: usize, tracker: &mut Tracker) {
+        self.row = row;
+        tracker.highlight(row);
+        self.rows = tracker.combatants()
+            .iter()
+            .map(|c| (
+                c.name().to_string(),
+                format!("AC {}", c.armor_class),
+                format!("({} / {})", c.hit_points(), c.max_hit_points()),
+            ))
+            .collect();
+    }
+
+    /// Makes the combatant at the given index the attacker.
+    fn set_attacker(&mut self, attacker: usize, tracker: &Tracker) {
+        self.attacker = attacker;
+        self.attacks = tracker.combatant(attacker)
+            .and_then(|c| c.stat_block.as_ref())
+            .map(|stat_block| stat_block.attacks())
+            .unwrap_or_default();
+        self.picked = None;
+    }
+
+    /// The attacker is the combatant whose turn it is, or the highlighted combatant before combat
+    /// starts. The highlighted combatant is targeted, or the next one if it is the attacker.
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        let len = tracker.combatants().len().max(1);
+        let attacker = tracker.turn().or(tracker.highlighted()).unwrap_or_default();
+        self.set_attacker(attacker, tracker);
+        let target = match tracker.highlighted() {
+            Some(highlighted) if highlighted != attacker => highlighted,
+            _ => (attacker + 1) % len,
+        };
+        self.set_row(target, tracker);
+    }
+
+    /// Rolls the entered attack against the target, applying damage on a hit and spending the
+    /// attacker's action. An attacker with no action left can still attack, such as with Extra
+    /// Attack, and this is logged.
+    ///
+    /// Returns an error if the attacker targets themselves.
+    fn roll(&mut self, bonus: i32, damage: Vec<TypedDamage>, tracker: &mut Tracker) -> Result<(), String> {
+        let (Some(attacker), Some(target)) = (tracker.combatant(self.attacker), tracker.combatant(self.row)) else {
+            return Ok(());
+        };
+        if self.attacker == self.row {
+            return Err(format!("{} can't attack themselves, pick another target", attacker.name()));
+        }
+        let attacker_name = attacker.name().to_string();
+        let target_name = target.name().to_string();
+        let armor_class = target.armor_class;
+
+        let attack_roll = Expression::d20(bonus, self.mode).roll(tracker.rng());
+        let natural = attack_roll.natural();
+        let critical = natural == Some(20);
+        let hit = critical || (natural != Some(1) && attack_roll.total() >= armor_class);
+        let rolls = if hit {
+            damage.iter().map(|damage| damage.roll(tracker.rng(), critical)).collect()
+        } else {
+            Vec::new()
+        };
+
+        let outcome = match (hit, critical, natural) {
+            (true, true, _) => "critical hit!",
+            (true, false, _) => "hit",
+            (false, _, Some(1)) => "natural 1, miss",
+            (false, _, _) => "miss",
+        };
+        let entry = format!("{} attacks {}: {}", attacker_name, target_name, outcome);
+        tracker.record(entry.clone());
+        tracker.log(entry);
+        // the limited action granted by Haste can be used for a single weapon attack
+        let spent = tracker.combatant_mut(self.attacker)
+            .is_some_and(|attacker| attacker.spend_action(Action::Action) || attacker.spend_action(Action::Limited));
+        if !spent {
+            tracker.log(format!("{} attacks with no action left", attacker_name));
+        }
+
+        self.last_attack = vec![format!("{} vs AC {}", attack_roll, armor_class)];
+        if !spent {
+            self.last_attack.push(format!("{} had no action left", attacker_name));
+        }
+        if hit {
+            self.last_attack.push(damage::format_rolls(&rolls));
+            let damage = rolls.into_iter().map(|(_, instance)| instance).collect::<Vec<_>>();
+            let taken = tracker.deal_damage(self.row, &damage, critical);
+            self.last_attack.push(format!("{}, {} takes {} damage", outcome, target_name, taken));
+        } else {
+            self.last_attack.push(outcome.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        let len = tracker.combatants().len().max(1);
+        let control = unhandled_key.modifiers.contains(KeyModifiers::CONTROL);
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                match parse_attack(self.input.as_str()).and_then(|(bonus, damage)| self.roll(bonus, damage, tracker)) {
+                    Ok(()) => {
+                        self.error = None;
+                        self.set_row(self.row, tracker);
+                        if tracker.has_concentration_checks() {
+                            return Some(State::ConcentrationCheck(ConcentrationCheck::default()));
+                        }
+                    },
+                    Err(error) => self.error = Some(error),
+                }
+                None
+            },
+            KeyCode::Tab => {
+                if !self.attacks.is_empty() {
+                    let idx = self.picked.map_or(0, |idx| (idx + 1) % self.attacks.len());
+                    let attack = &self.attacks[idx];
+                    self.input.set(format!("{:+} {}", attack.bonus, attack.damage));
+                    self.picked = Some(idx);
+                }
+                None
+            },
+            KeyCode::BackTab => {
+                self.mode = self.mode.next();
+                None
+            },
+            KeyCode::Char('n') if control => {
+                self.set_attacker((self.attacker + 1) % len, tracker);
+                None
+            },
+            KeyCode::Char('p') if control => {
+                self.set_attacker((self.attacker + len - 1) % len, tracker);
+                None
+            },
+            KeyCode::Down => {
+                self.set_row((self.row + 1) % len, tracker);
+                None
+            },
+            KeyCode::Up => {
+                self.set_row((self.row + len - 1) % len, tracker);
+                None
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combatant::Combatant;
+
+    fn tracker() -> Tracker {
+        let mut tracker = Tracker::new();
+        for (name, hit_points, initiative) in [("Ayla", 10, 15), ("Goblin", 7, 12)] {
+            let mut combatant = Combatant::new(name.to_string(), hit_points, hit_points);
+            combatant.set_initiative(initiative);
+            tracker.add_combatant(combatant);
+        }
+        tracker
+    }
+
+    #[test]
+    fn cannot_attack_themselves() {
+        let mut tracker = tracker();
+        let mut attack = Attack::default();
+        assert!(attack.roll(5, Vec::new(), &mut tracker).is_err());
+        assert_eq!(tracker.history().next_undo(), Some("add Goblin"));
+        assert!(attack.last_attack.is_empty());
+    }
+
+    #[test]
+    fn attacking_without_an_action_is_logged() {
+        let mut tracker = tracker();
+        let mut attack = Attack { row: 1, ..Default::default() };
+        attack.roll(5, Vec::new(), &mut tracker).unwrap();
+        assert!(!tracker.log_entries().iter().any(|entry| entry.contains("no action left")));
+        attack.roll(5, Vec::new(), &mut tracker).unwrap();
+        assert!(tracker.log_entries().iter().any(|entry| entry == "Ayla attacks with no action left"));
+        assert_eq!(attack.last_attack[1], "Ayla had no action left");
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    ability::Ability,
+    dice::{Expression, Roll, RollMode},
+    input::Input,
+    state::{Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Prompting a damaged combatant for the Constitution saving throw to keep concentrating on their
+/// spell. The DC is 10 or half the damage taken, whichever is higher.
+#[derive(Default, PartialEq, Eq)]
+pub struct ConcentrationCheck {
+    /// The index of the concentrating combatant and the DC of the saving throw, or [`None`] if no
+    /// combatant needs to make one.
+    pub check: Option<(usize, i32)>,
+
+    /// The name of the combatant, the spell they are concentrating on, and their Constitution
+    /// saving throw modifier.
+    caster: (String, String, i32),
+
+    /// Whether the saving throw is rolled with advantage or disadvantage, such as from the War
+    /// Caster feat.
+    pub mode: RollMode,
+
+    /// The error from parsing the entered roll, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl ConcentrationCheck {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        "<escape>: skip the saving throw\n<enter>: roll the saving throw, or enter the total rolled\n<shift-tab>: cycle advantage / disadvantage".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let (name, spell, modifier) = &self.caster;
+        let rows = match self.check {
+            Some((_, dc)) => vec![
+                Row::new([Text::from("Spell").bold(), Text::from(spell.as_str())]),
+                Row::new([Text::from("DC").bold(), Text::from(dc.to_string())]),
+                Row::new([Text::from("Modifier").bold(), Text::from(format!("{:+} {}", modifier, self.mode.label()))]),
+            ],
+            None => vec![Row::new([Text::from("No combatant needs to keep concentrating."), Text::from("")])],
+        };
+        Table::new(
+            rows.into_iter().chain(self.error.as_ref().map(|error| {
+                Row::new([Text::from("Error").bold(), Text::from(error.as_str())]).red()
+            })),
+            [Constraint::Length(10), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(format!("Concentration - {}", name))
+            )
+    }
+
+    /// Takes the next concentration saving throw that a damaged combatant must make.
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        self.check = tracker.next_concentration_check();
+        let Some((idx, _)) = self.check else {
+            return;
+        };
+        if let Some(combatant) = tracker.combatant(idx) {
+            let spell = combatant.concentration.as_ref().map(|c| c.spell.clone()).unwrap_or_default();
+            self.caster = (combatant.name().to_string(), spell, combatant.save_modifier(Ability::Constitution));
+        }
+        tracker.highlight(idx);
+    }
+
+    /// Resolves the saving throw with the given total, ending the combatant's concentration if
+    /// it fails.
+    fn resolve(&mut self, total: i32, roll: Option<Roll>, tracker: &mut Tracker) {
+        let Some((idx, dc)) = self.check else {
+            return;
+        };
+        let (name, spell, _) = &self.caster;
+        let roll = roll.map_or_else(|| total.to_string(), |roll| roll.to_string());
+        if total >= dc {
+            tracker.log(format!("{} keeps concentrating on {} ({} vs DC {})", name, spell, roll, dc));
+        } else {
+            tracker.record(format!("{} loses concentration on {}", name, spell));
+            tracker.log(format!("{} fails to keep concentrating ({} vs DC {})", name, roll, dc));
+            tracker.break_concentration(idx);
+        }
+    }
+
+    /// Moves on to the next concentration saving throw, if any.
+    fn next(tracker: &Tracker) -> State {
+        if tracker.has_concentration_checks() {
+            State::ConcentrationCheck(ConcentrationCheck::default())
+        } else {
+            State::Home(Home::default())
+        }
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        match unhandled_key.code {
+            KeyCode::Esc => Some(Self::next(tracker)),
+            KeyCode::Enter => {
+                let Some((idx, _)) = self.check else {
+                    return Some(Self::next(tracker));
+                };
+                if self.input.is_empty() {
+                    let (_, _, modifier) = self.caster;
+                    // an incapacitated combatant has already lost concentration, so the save is
+                    // never failed automatically here
+                    let conditions = tracker.combatant(idx)
+                        .and_then(|c| c.save_mode(Ability::Constitution))
+                        .unwrap_or_default();
+                    let roll = Expression::d20(modifier, conditions.combine(self.mode)).roll(tracker.rng());
+                    self.resolve(roll.total(), Some(roll), tracker);
+                } else {
+                    let input = self.input.as_str().trim();
+                    match input.strip_prefix('+').unwrap_or(input).parse() {
+                        Ok(total) => self.resolve(total, None, tracker),
+                        Err(_) => {
+                            self.error = Some("expected the total of the saving throw".to_string());
+                            return None;
+                        },
+                    }
+                }
+                Some(Self::next(tracker))
+            },
+            KeyCode::BackTab => {
+                self.mode = self.mode.next();
+                None
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    combatant::Concentration,
+    input::Input,
+    state::{Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Starting or ending a combatant's concentration on a spell, and choosing the spell's targets.
+#[derive(Default, PartialEq, Eq)]
+pub struct Concentrate {
+    /// The index of the concentrating combatant.
+    pub caster: usize,
+
+    /// The indices of the combatants targeted by the spell.
+    pub targets: Vec<usize>,
+
+    /// The index of the currently focused combatant.
+    pub row: usize,
+
+    /// The name of each combatant, for display.
+    rows: Vec<String>,
+
+    /// The spell the caster is already concentrating on, if any.
+    pub current: Option<String>,
+
+    input: Input,
+}
+
+impl Concentrate {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        let mut help = "<escape>: back to initiative tracker\n<enter>: concentrate on the entered spell, affecting the targets\n<tab>: toggle focused combatant as target\n<up>: previous combatant\n<down>: next combatant".to_string();
+        if self.current.is_some() {
+            help.push_str("\n<enter> (empty): stop concentrating, ending the spell's conditions");
+        }
+        help
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let caster = self.rows.get(self.caster).map(String::as_str).unwrap_or("Unknown");
+        let title = match &self.current {
+            Some(spell) => format!("{} is concentrating on {}", caster, spell),
+            None => format!("{} concentrates", caster),
+        };
+        Table::new(
+            self.rows.iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let marker = if self.targets.contains(&i) { "[x]" } else { "[ ]" };
+                    let row = Row::new([Text::from(marker), Text::from(name.as_str())]);
+                    if i == self.row {
+                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                    } else {
+                        row
+                    }
+                }),
+            [Constraint::Length(4), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(title)
+            )
+    }
+
+    pub fn set_row(&mut self, row: usize, tracker: &mut Tracker) {
+        self.row = row;
+        tracker.highlight(row);
+    }
+
+    /// The highlighted combatant concentrates, starting with the targets of the spell they are
+    /// already concentrating on.
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        self.caster = tracker.highlighted().unwrap_or_default();
+        self.rows = tracker.combatants().iter().map(|c| c.name().to_string()).collect();
+        if let Some(concentration) = tracker.combatant(self.caster).and_then(|c| c.concentration.as_ref()) {
+            self.current = Some(concentration.spell.clone());
+            self.input.set(concentration.spell.clone());
+            self.targets = concentration.targets.iter()
+                .filter_map(|&id| tracker.combatants().iter().position(|c| c.id == id))
+                .collect();
+        }
+        self.set_row(self.caster, tracker);
+    }
+
+    /// Starts concentrating on the entered spell, ending any previous concentration unless it is
+    /// the same spell, or stops concentrating if no spell is entered.
+    fn apply(&mut self, tracker: &mut Tracker) {
+        let spell = self.input.take().trim().to_string();
+        let Some(caster) = tracker.combatant(self.caster) else {
+            return;
+        };
+        let name = caster.name().to_string();
+        let targets = self.targets.iter()
+            .filter_map(|&idx| tracker.combatant(idx))
+            .map(|c| c.id)
+            .collect();
+
+        if spell.is_empty() {
+            if self.current.is_some() {
+                tracker.record(format!("{} stops concentrating", name));
+                tracker.break_concentration(self.caster);
+            }
+            return;
+        }
+        if self.current.as_ref() == Some(&spell) {
+            tracker.record(format!("change targets of {}'s {}", name, spell));
+        } else {
+            tracker.record(format!("{} concentrates on {}", name, spell));
+            tracker.break_concentration(self.caster);
+            tracker.log(format!("{} starts concentrating on {}", name, spell));
+        }
+        if let Some(caster) = tracker.combatant_mut(self.caster) {
+            caster.concentration = Some(Concentration { spell, targets });
+        }
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        let len = tracker.combatants().len().max(1);
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                self.apply(tracker);
+                Some(State::Home(Home::default()))
+            },
+            KeyCode::Tab => {
+                if let Some(pos) = self.targets.iter().position(|&idx| idx == self.row) {
+                    self.targets.remove(pos);
+                } else if self.row < tracker.combatants().len() {
+                    self.targets.push(self.row);
+                }
+                None
+            },
+            KeyCode::Down => {
+                self.set_row((self.row + 1) % len, tracker);
+                None
+            },
+            KeyCode::Up => {
+                self.set_row((self.row + len - 1) % len, tracker);
+                None
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{
+    condition::{AppliedCondition, Condition, Duration},
+    input::Input,
+    state::{Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Adding conditions to and removing conditions from a combatant.
+#[derive(Default, PartialEq, Eq)]
+pub struct Conditions {
+    /// The index of the combatant whose conditions are being edited.
+    pub combatant: usize,
+
+    /// The name of the combatant whose conditions are being edited.
+    pub name: Option<String>,
+
+    /// The conditions currently affecting the combatant.
+    pub conditions: Vec<AppliedCondition>,
+
+    /// The index of the currently focused condition in [`Condition::ALL`].
+    pub row: usize,
+
+    /// The error from the last entered duration or exhaustion level, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl Conditions {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        match Condition::ALL[self.row] {
+            Condition::Exhaustion(_) => "<escape>: back to initiative tracker\n<enter>: set exhaustion level from 1 to 6, where 6 is death (empty to toggle, 0 to remove)\n<tab>: next combatant\n<shift-tab>: previous combatant\n<up>: previous condition\n<down>: next condition",
+            _ => "<escape>: back to initiative tracker\n<enter>: toggle condition, lasting until removed (empty), for <n> rounds, until the end of the current combatant's next turn (e), or while the current combatant concentrates (c)\n<tab>: next combatant\n<shift-tab>: previous combatant\n<up>: previous condition\n<down>: next condition",
+        }.to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let error = self.error.as_ref().map(|error| {
+            Row::new([Text::from(""), Text::from(error.as_str())]).red()
+        });
+        Table::new(
+            Condition::ALL.iter()
+                .enumerate()
+                .map(|(i, condition)| {
+                    let applied = self.conditions.iter()
+                        .find(|applied| applied.condition.same_kind(condition));
+                    let marker = if applied.is_some() { "[x]" } else { "[ ]" };
+                    let text = applied.map(|applied| applied.to_string())
+                        .unwrap_or_else(|| condition.to_string());
+                    let row = Row::new([Text::from(marker), Text::from(text)]);
+                    if i == self.row {
+                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                    } else {
+                        row
+                    }
+                })
+                .chain(error),
+            [Constraint::Length(4), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(format!("Conditions of {}", self.name.as_deref().unwrap_or("Unknown")))
+            )
+    }
+
+    pub fn set_combatant(&mut self, combatant: usize, tracker: &mut Tracker) {
+        self.combatant = combatant;
+        tracker.highlight(combatant);
+        self.refresh(tracker);
+    }
+
+    /// Updates the displayed name and conditions of the combatant.
+    fn refresh(&mut self, tracker: &Tracker) {
+        let combatant = tracker.combatant(self.combatant);
+        self.name = combatant.map(|c| c.name().to_string());
+        self.conditions = combatant.map(|c| c.conditions().to_vec()).unwrap_or_default();
+    }
+
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        self.set_combatant(tracker.highlighted().unwrap_or_default(), tracker);
+    }
+
+    /// Toggles the focused condition on the combatant, using the input to determine the duration
+    /// of the condition, or the level of exhaustion. Invalid input is kept, with an error.
+    fn toggle(&mut self, tracker: &mut Tracker) {
+        match self.try_toggle(tracker) {
+            Ok(()) => {
+                self.input.take();
+                self.error = None;
+            },
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    /// Toggles the focused condition on the combatant, returning an error if the input is
+    /// invalid.
+    fn try_toggle(&mut self, tracker: &mut Tracker) -> Result<(), String> {
+        let input = self.input.as_str().trim();
+        let condition = Condition::ALL[self.row];
+        let source = tracker.turn().and_then(|turn| tracker.combatant(turn));
+        let concentration = source.filter(|c| c.concentration.is_some()).map(|c| c.id);
+        let source = source.map(|c| c.id);
+        let Some(combatant) = tracker.combatant(self.combatant) else {
+            return Ok(());
+        };
+
+        let new_condition = match condition {
+            Condition::Exhaustion(_) => {
+                let level = if input.is_empty() {
+                    u8::from(combatant.condition(&condition).is_none())
+                } else {
+                    input.parse::<u8>()
+                        .ok()
+                        .filter(|&level| level <= Condition::MAX_EXHAUSTION)
+                        .ok_or_else(|| format!("exhaustion level must be a number from 0 to {}", Condition::MAX_EXHAUSTION))?
+                };
+                (level > 0).then_some(Condition::Exhaustion(level))
+            },
+            _ if combatant.condition(&condition).is_some() && input.is_empty() => None,
+            _ => Some(condition),
+        };
+        let concentration = match (new_condition, input) {
+            // only a concentrating combatant can maintain a condition
+            (Some(_), "c") if concentration.is_none() => {
+                return Err("the current combatant is not concentrating".to_string());
+            },
+            (Some(_), "c") => concentration,
+            _ => None,
+        };
+        let duration = match (new_condition, input) {
+            (None, _) | (Some(Condition::Exhaustion(_)), _) | (_, "") | (_, "c") => None,
+            // the condition is applied during the source's turn, which must end first
+            (_, "e") => match source {
+                Some(source) => Some(Duration::EndOfSourceTurn { source, turns: 2 }),
+                None => return Err("combat has not started, so there is no current turn to end".to_string()),
+            },
+            (_, rounds) => match rounds.parse::<u32>() {
+                Ok(rounds) if rounds > 0 => Some(Duration::Rounds(rounds)),
+                _ => return Err("expected a number of rounds, e, or c".to_string()),
+            },
+        };
+
+        let (id, name, was_dead) = (combatant.id, combatant.name().to_string(), combatant.is_dead());
+        tracker.record(format!("change conditions of {}", name));
+        let Some(combatant) = tracker.combatant_mut(self.combatant) else {
+            return Ok(());
+        };
+        match new_condition {
+            Some(condition) => combatant.add_condition(AppliedCondition { condition, duration, concentration }),
+            None => combatant.remove_condition(&condition),
+        }
+        let died = !was_dead && combatant.is_dead();
+        if died {
+            tracker.log(format!("{} dies from exhaustion", name));
+        }
+
+        // an incapacitated or dead combatant loses concentration
+        if died || new_condition.is_some_and(|condition| condition.incapacitates()) {
+            tracker.break_concentration(self.combatant);
+        }
+        let caster = concentration.and_then(|caster| tracker.combatants().iter().position(|c| c.id == caster));
+        let spell = caster.and_then(|caster| tracker.combatant_mut(caster)?.concentration.as_mut());
+        if let Some(spell) = spell.filter(|spell| !spell.targets.contains(&id)) {
+            spell.targets.push(id);
+        }
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        let len = tracker.combatants().len().max(1);
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                self.toggle(tracker);
+                self.refresh(tracker);
+                None
+            },
+            KeyCode::Tab => {
+                self.set_combatant((self.combatant + 1) % len, tracker);
+                None
+            },
+            KeyCode::BackTab => {
+                self.set_combatant((self.combatant + len - 1) % len, tracker);
+                None
+            },
+            KeyCode::Down => {
+                self.row = (self.row + 1) % Condition::ALL.len();
+                None
+            },
+            KeyCode::Up => {
+                self.row = (self.row + Condition::ALL.len() - 1) % Condition::ALL.len();
+                None
+            },
+            _ => None,
+        }
+    }
+}
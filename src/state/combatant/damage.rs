@@ -0,0 +1,226 @@
+use crate::{
+    damage::{self, DamageInstance, TypedDamage},
+    dice::{self, Roll},
+    input::Input,
+    state::{combatant::check::ConcentrationCheck, Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// How the entered amount affects the targeted combatants.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum DamageKind {
+    /// Deal damage, draining temporary hit points first.
+    #[default]
+    Damage,
+
+    /// Heal, up to the combatant's maximum hit points.
+    Heal,
+
+    /// Grant temporary hit points, keeping the higher of the new and existing amounts.
+    TempHitPoints,
+}
+
+impl DamageKind {
+    /// Returns the next kind, cycling back to the first.
+    pub fn next(self) -> DamageKind {
+        match self {
+            DamageKind::Damage => DamageKind::Heal,
+            DamageKind::Heal => DamageKind::TempHitPoints,
+            DamageKind::TempHitPoints => DamageKind::Damage,
+        }
+    }
+
+    /// Returns the name of the kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageKind::Damage => "Damage",
+            DamageKind::Heal => "Heal",
+            DamageKind::TempHitPoints => "Temporary HP",
+        }
+    }
+}
+
+/// Deal damage to, heal, or grant temporary hit points to one or more combatants.
+#[derive(Default, PartialEq, Eq)]
+pub struct Damage {
+    /// How the entered amount affects the targets.
+    pub kind: DamageKind,
+
+    /// The indices of the combatants that will be affected.
+    pub targets: Vec<usize>,
+
+    /// The index of the currently focused combatant.
+    pub row: usize,
+
+    /// The name and hit points of each combatant, for display.
+    rows: Vec<(String, String)>,
+
+    /// The last rolled amount, and a description of what it was applied to.
+    pub last_roll: Option<String>,
+
+    /// The error from parsing the last entered amount, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl Damage {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        "<escape>: back to initiative tracker\n<enter>: apply amount (number or dice, e.g. 2d6+3) to targets, optionally with damage types (e.g. 2d6+3 slashing plus 1d6 fire)\n<tab>: toggle focused combatant as target\n<shift-tab>: switch between damage / heal / temporary hp\n<up>: previous combatant\n<down>: next combatant".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let result = match (&self.error, &self.last_roll) {
+            (Some(error), _) => Some(Row::new([Text::from("Error"), Text::from(error.as_str())]).red()),
+            (None, Some(last_roll)) => Some(Row::new([Text::from("Last"), Text::from(last_roll.as_str())])),
+            (None, None) => None,
+        };
+        Table::new(
+            self.rows.iter()
+                .enumerate()
+                .map(|(i, (name, hp))| {
+                    let marker = if self.targets.contains(&i) { "[x]" } else { "[ ]" };
+                    let row = Row::new([Text::from(marker), Text::from(name.as_str()), Text::from(hp.as_str())]);
+                    if i == self.row {
+                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                    } else {
+                        row
+                    }
+                })
+                .chain(result),
+            [Constraint::Length(6), Constraint::Fill(1), Constraint::Length(14)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(self.kind.name())
+            )
+    }
+
+    pub fn set_row(&mut self, row: usize, tracker: &mut Tracker) {
+        self.row = row;
+        tracker.highlight(row);
+        self.rows = tracker.combatants()
+            .iter()
+            .map(|c| {
+                let hp = format!("{} / {} (+{})", c.hit_points(), c.max_hit_points(), c.temp_hit_points());
+                (c.name().to_string(), hp)
+            })
+            .collect();
+    }
+
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        let row = tracker.highlighted().unwrap_or_default();
+        if tracker.combatant(row).is_some() {
+            self.targets = vec![row];
+        }
+        self.set_row(row, tracker);
+    }
+
+    /// Rolls the entered amount. Damage may be split into several types, while healing and
+    /// temporary hit points are a single amount.
+    fn roll(&self, tracker: &mut Tracker) -> Result<Vec<(Roll, DamageInstance)>, String> {
+        match self.kind {
+            DamageKind::Damage => {
+                let damage = TypedDamage::parse_all(self.input.as_str())?;
+                Ok(damage.iter().map(|damage| damage.roll(tracker.rng(), false)).collect())
+            },
+            DamageKind::Heal | DamageKind::TempHitPoints => {
+                let roll = dice::roll(self.input.as_str(), tracker.rng()).map_err(|error| error.to_string())?;
+                let instance = DamageInstance { amount: roll.total().max(0), kind: None, magical: false };
+                Ok(vec![(roll, instance)])
+            },
+        }
+    }
+
+    /// Applies the rolled amount to every target, or to the focused combatant if there are no
+    /// targets. Damage is adjusted by each target's resistances, vulnerabilities and immunities.
+    fn apply(&mut self, rolls: Vec<(Roll, DamageInstance)>, tracker: &mut Tracker) {
+        let amount = rolls.iter().map(|(_, instance)| instance.amount).fold(0, i32::saturating_add);
+        let targets = super::take_targets(&mut self.targets, self.row, tracker);
+        if targets.is_empty() {
+            return;
+        }
+        let names = targets.iter()
+            .filter_map(|&idx| tracker.combatant(idx))
+            .map(|c| c.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let description = match self.kind {
+            DamageKind::Damage => {
+                let damage = rolls.iter().map(|(_, instance)| instance.to_string()).collect::<Vec<_>>().join(" plus ");
+                format!("deal {} damage to {}", damage, names)
+            },
+            DamageKind::Heal => format!("heal {} for {}", names, amount),
+            DamageKind::TempHitPoints => format!("grant {} temp hp to {}", amount, names),
+        };
+        tracker.record(description.clone());
+        self.last_roll = Some(format!("{} ({})", damage::format_rolls(&rolls), description));
+
+        let damage = rolls.into_iter().map(|(_, instance)| instance).collect::<Vec<_>>();
+        for idx in targets {
+            match (self.kind, tracker.combatant_mut(idx)) {
+                (DamageKind::Damage, Some(_)) => {
+                    tracker.deal_damage(idx, &damage, false);
+                },
+                (DamageKind::Heal, Some(combatant)) => combatant.heal(amount),
+                (DamageKind::TempHitPoints, Some(combatant)) => combatant.grant_temp_hit_points(amount),
+                (_, None) => (),
+            }
+        }
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        let len = tracker.combatants().len().max(1);
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                match self.roll(tracker) {
+                    Ok(rolls) => {
+                        self.input.take();
+                        self.error = None;
+                        self.apply(rolls, tracker);
+                        self.set_row(self.row, tracker);
+                        // damaged casters must make a saving throw to keep concentrating
+                        if tracker.has_concentration_checks() {
+                            return Some(State::ConcentrationCheck(ConcentrationCheck::default()));
+                        }
+                    },
+                    Err(error) => self.error = Some(error),
+                }
+                None
+            },
+            KeyCode::Tab => {
+                if let Some(pos) = self.targets.iter().position(|&idx| idx == self.row) {
+                    self.targets.remove(pos);
+                } else if self.row < tracker.combatants().len() {
+                    self.targets.push(self.row);
+                }
+                None
+            },
+            KeyCode::BackTab => {
+                self.kind = self.kind.next();
+                None
+            },
+            KeyCode::Down => {
+                self.set_row((self.row + 1) % len, tracker);
+                None
+            },
+            KeyCode::Up => {
+                self.set_row((self.row + len - 1) % len, tracker);
+                None
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    combatant::{DeathSaveResult, DeathSaves},
+    dice::{Expression, RollMode},
+    input::Input,
+    state::{Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Parses a death saving throw entered as the natural d20 roll.
+fn parse_natural(input: &str) -> Result<u32, String> {
+    input.trim()
+        .parse()
+        .ok()
+        .filter(|natural| (1..=20).contains(natural))
+        .ok_or_else(|| "expected the d20 roll, from 1 to 20".to_string())
+}
+
+/// Recording a death saving throw for a dying player character at the start of their turn.
+#[derive(Default, PartialEq, Eq)]
+pub struct DeathSave {
+    /// The index of the dying combatant.
+    pub row: Option<usize>,
+
+    /// The name of the dying combatant.
+    pub name: String,
+
+    /// The death saving throws the combatant has made so far.
+    pub saves: DeathSaves,
+
+    /// The error from parsing the entered roll, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl DeathSave {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        "<escape>: skip, back to initiative tracker\n<enter>: record death saving throw, entered as the d20 roll (empty to roll it)\n\n10 or higher succeeds, a 1 counts as two failures, and a 20 regains 1 hit point".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let title = match self.row {
+            Some(_) => format!("Death Saving Throw - {}", self.name),
+            None => "Death Saving Throw".to_string(),
+        };
+        let rows = match self.row {
+            Some(_) => vec![
+                Row::new([Text::from("Successes").bold(), Text::from(format!("{} / 3", self.saves.successes))]).green(),
+                Row::new([Text::from("Failures").bold(), Text::from(format!("{} / 3", self.saves.failures))]).red(),
+            ],
+            None => vec![Row::new([Text::from("No combatant is dying."), Text::from("")])],
+        };
+        Table::new(
+            rows.into_iter().chain(self.error.as_ref().map(|error| {
+                Row::new([Text::from("Error").bold(), Text::from(error.as_str())]).red()
+            })),
+            [Constraint::Length(12), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(title)
+            )
+    }
+
+    /// The combatant whose turn it is rolls, or the highlighted combatant if it isn't a dying
+    /// combatant's turn.
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        self.row = [tracker.turn(), tracker.highlighted()]
+            .into_iter()
+            .flatten()
+            .find(|&idx| tracker.combatant(idx).is_some_and(|c| c.is_dying()));
+        if let Some(combatant) = self.row.and_then(|idx| tracker.combatant(idx)) {
+            self.name = combatant.name().to_string();
+            self.saves = combatant.death_saves();
+        }
+        if let Some(row) = self.row {
+            tracker.highlight(row);
+        }
+    }
+
+    /// Records the death saving throw with the given natural roll, and logs its outcome.
+    fn record(&mut self, row: usize, natural: u32, tracker: &mut Tracker) {
+        tracker.record(format!("death saving throw for {}", self.name));
+        let Some(combatant) = tracker.combatant_mut(row) else {
+            return;
+        };
+        let result = combatant.death_save(natural);
+        let saves = combatant.death_saves();
+        let outcome = match result {
+            DeathSaveResult::Success => format!("success ({} / 3)", saves.successes),
+            DeathSaveResult::Failure if natural == 1 => format!("natural 1, two failures ({} / 3)", saves.failures),
+            DeathSaveResult::Failure => format!("failure ({} / 3)", saves.failures),
+            DeathSaveResult::Stable => "third success, stable".to_string(),
+            DeathSaveResult::Died => "third failure, dies".to_string(),
+            DeathSaveResult::Revived => "natural 20, regains 1 hit point".to_string(),
+        };
+        tracker.log(format!("{} rolls a death saving throw: {}, {}", self.name, natural, outcome));
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                let Some(row) = self.row else {
+                    return Some(State::Home(Home::default()));
+                };
+                let natural = if self.input.is_empty() {
+                    Expression::d20(0, RollMode::Normal).roll(tracker.rng()).natural().unwrap_or_default()
+                } else {
+                    match parse_natural(self.input.as_str()) {
+                        Ok(natural) => natural,
+                        Err(error) => {
+                            self.error = Some(error);
+                            return None;
+                        },
+                    }
+                };
+                self.record(row, natural, tracker);
+                Some(State::Home(Home::default()))
+            },
+            _ => None,
+        }
+    }
+}
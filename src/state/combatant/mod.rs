@@ -1 +1,23 @@
 pub mod add;
+pub mod attack;
+pub mod check;
+pub mod concentration;
+pub mod conditions;
+pub mod damage;
+pub mod death;
+pub mod remove;
+pub mod resources;
+pub mod save;
+
+use crate::tracker::Tracker;
+
+/// Takes the marked targets of an effect, or the focused combatant at `row` if none are marked,
+/// leaving no combatant marked for the next effect. Targets that have left the tracker are dropped.
+fn take_targets(targets: &mut Vec<usize>, row: usize, tracker: &Tracker) -> Vec<usize> {
+    let mut targets = match std::mem::take(targets) {
+        targets if targets.is_empty() => vec![row],
+        targets => targets,
+    };
+    targets.retain(|&idx| idx < tracker.combatants().len());
+    targets
+}
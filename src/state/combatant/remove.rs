@@ -0,0 +1,58 @@
+use crate::{state::{Home, State}, tracker::Tracker};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Removing the highlighted combatant from the initiative order, after confirmation.
+#[derive(Default, PartialEq, Eq)]
+pub struct RemoveCombatant {
+    /// The index of the combatant to remove.
+    pub row: Option<usize>,
+
+    /// The name of the combatant to remove.
+    pub name: String,
+}
+
+impl RemoveCombatant {
+    pub fn help(&self) -> String {
+        "y: remove the combatant\nn: cancel, back to initiative tracker".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let text = match self.row {
+            Some(_) => format!("Remove {} from the initiative order?", self.name),
+            None => "No combatant is highlighted.".to_string(),
+        };
+        Table::new(
+            [Row::new([Text::from(text)])],
+            [Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title("Remove Combatant")
+            )
+    }
+
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        self.row = tracker.highlighted().filter(|&idx| idx < tracker.combatants().len());
+        self.name = self.row
+            .and_then(|idx| tracker.combatant(idx))
+            .map(|c| c.name().to_string())
+            .unwrap_or_default();
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        match key.code {
+            KeyCode::Char('y') => {
+                if let Some(row) = self.row.take() {
+                    tracker.remove_combatant(row);
+                }
+                Some(State::Home(Home::default()))
+            },
+            KeyCode::Char('n') | KeyCode::Esc => Some(State::Home(Home::default())),
+            _ => None,
+        }
+    }
+}
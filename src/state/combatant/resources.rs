@@ -0,0 +1,219 @@
+use crate::{
+    input::Input,
+    resource::{self, Resource},
+    state::{Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// A change to the uses left of a resource.
+#[derive(Debug, PartialEq, Eq)]
+enum Change {
+    /// Spend the given number of uses.
+    Spend(u32),
+
+    /// Restore the given number of uses.
+    Restore(u32),
+
+    /// Set the uses left directly.
+    Set(u32),
+
+    /// Restore every use.
+    Full,
+}
+
+impl Change {
+    /// Parses a change entered as `-N`, `+N`, `N` or `max`. An empty input spends one use.
+    fn parse(input: &str) -> Option<Change> {
+        let input = input.trim();
+        match input {
+            "" => Some(Change::Spend(1)),
+            "max" => Some(Change::Full),
+            _ => if let Some(amount) = input.strip_prefix('-') {
+                amount.parse().ok().map(Change::Spend)
+            } else if let Some(amount) = input.strip_prefix('+') {
+                amount.parse().ok().map(Change::Restore)
+            } else {
+                input.parse().ok().map(Change::Set)
+            },
+        }
+    }
+
+    /// Returns the uses left after applying the change to the resource.
+    fn apply(&self, resource: &Resource) -> u32 {
+        match *self {
+            Change::Spend(amount) => resource.current.saturating_sub(amount),
+            Change::Restore(amount) => resource.current.saturating_add(amount).min(resource.max),
+            Change::Set(amount) => amount.min(resource.max),
+            Change::Full => resource.max,
+        }
+    }
+}
+
+/// Spending and restoring a combatant's limited-use resources, such as spell slots and ki
+/// points.
+#[derive(Default, PartialEq, Eq)]
+pub struct Resources {
+    /// The index of the combatant whose resources are being changed.
+    pub combatant: usize,
+
+    /// The name of the combatant whose resources are being changed.
+    pub name: Option<String>,
+
+    /// The resources of the combatant.
+    pub resources: Vec<Resource>,
+
+    /// The index of the currently focused resource.
+    pub row: usize,
+
+    /// The error from parsing the last entered change, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl Resources {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        "<escape>: back to initiative tracker\n<enter>: spend one use (empty), spend <n> (-n), restore <n> (+n), set uses left (n), or restore all (max)\n<tab>: next combatant\n<shift-tab>: previous combatant\n<up>: previous resource\n<down>: next resource\n\nresources are set when adding or editing a combatant, and spent abilities that recharge are rolled for at the start of their turn".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let rows = if self.resources.is_empty() {
+            vec![Row::new([Text::from("No resources."), Text::from("")])]
+        } else {
+            self.resources.iter()
+                .enumerate()
+                .map(|(i, resource)| {
+                    let row = Row::new([
+                        Text::from(resource.label()),
+                        Text::from(match resource.recharge() {
+                            Some(_) if resource.current == 0 => "spent".to_string(),
+                            Some(_) => "ready".to_string(),
+                            None => format!("{} / {}", resource.current, resource.max),
+                        }),
+                    ]);
+                    if i == self.row {
+                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                    } else {
+                        row
+                    }
+                })
+                .collect()
+        };
+        Table::new(
+            rows.into_iter().chain(self.error.as_ref().map(|error| {
+                Row::new([Text::from("Error"), Text::from(error.as_str())]).red()
+            })),
+            [Constraint::Fill(1), Constraint::Length(10)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title(format!("Resources of {}", self.name.as_deref().unwrap_or("Unknown")))
+            )
+    }
+
+    pub fn set_combatant(&mut self, combatant: usize, tracker: &mut Tracker) {
+        self.combatant = combatant;
+        self.row = 0;
+        tracker.highlight(combatant);
+        self.refresh(tracker);
+    }
+
+    /// Updates the displayed name and resources of the combatant.
+    fn refresh(&mut self, tracker: &Tracker) {
+        let combatant = tracker.combatant(self.combatant);
+        self.name = combatant.map(|c| c.name().to_string());
+        self.resources = combatant.map(|c| c.resources.clone()).unwrap_or_default();
+    }
+
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        self.set_combatant(tracker.highlighted().unwrap_or_default(), tracker);
+    }
+
+    /// Applies the entered change to the focused resource, and logs it.
+    fn change(&mut self, tracker: &mut Tracker) {
+        let Some(change) = Change::parse(self.input.as_str()) else {
+            self.error = Some("expected -n, +n, n or max".to_string());
+            return;
+        };
+        self.input.take();
+        self.error = None;
+        let (Some(name), Some(resource)) = (self.name.clone(), self.resources.get(self.row)) else {
+            return;
+        };
+        let current = change.apply(resource);
+        if current == resource.current {
+            return;
+        }
+
+        let entry = match (change, resource.recharge()) {
+            (Change::Spend(_), Some(min)) => format!("{} uses {}, which recharges on {}", name, resource.label(), resource::format_recharge(min)),
+            (_, Some(_)) => format!("{} has {} ready again", name, resource.label()),
+            (Change::Spend(1), None) if current + 1 == resource.current => {
+                format!("{} spends {} ({} / {} left)", name, resource.use_description(), current, resource.max)
+            },
+            (_, None) => format!("{} has {} / {} {} left", name, current, resource.max, resource.label()),
+        };
+        tracker.record(format!("change {} of {}", resource.label(), name));
+        tracker.log(entry);
+        if let Some(resource) = tracker.combatant_mut(self.combatant).and_then(|c| c.resources.get_mut(self.row)) {
+            resource.current = current;
+        }
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        let len = tracker.combatants().len().max(1);
+        let resources = self.resources.len().max(1);
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                self.change(tracker);
+                self.refresh(tracker);
+                None
+            },
+            KeyCode::Tab => {
+                self.set_combatant((self.combatant + 1) % len, tracker);
+                None
+            },
+            KeyCode::BackTab => {
+                self.set_combatant((self.combatant + len - 1) % len, tracker);
+                None
+            },
+            KeyCode::Down => {
+                self.row = (self.row + 1) % resources;
+                None
+            },
+            KeyCode::Up => {
+                self.row = (self.row + resources - 1) % resources;
+                None
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_change() {
+        assert_eq!(Change::parse(""), Some(Change::Spend(1)));
+        assert_eq!(Change::parse("-2"), Some(Change::Spend(2)));
+        assert_eq!(Change::parse("+3"), Some(Change::Restore(3)));
+        assert_eq!(Change::parse("4"), Some(Change::Set(4)));
+        assert_eq!(Change::parse("max"), Some(Change::Full));
+        assert_eq!(Change::parse("é1"), None);
+        assert_eq!(Change::parse("-"), None);
+    }
+}
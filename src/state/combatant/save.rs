@@ -0,0 +1,256 @@
+use crate::{
+    ability::Ability,
+    damage::{self, TypedDamage},
+    dice::{Expression, Roll, RollMode},
+    input::Input,
+    state::{combatant::check::ConcentrationCheck, Home, State},
+    tracker::Tracker,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// A saving throw to roll for every target, and the damage dealt by the effect that forced it.
+struct Effect {
+    /// The ability the saving throw uses.
+    ability: Ability,
+
+    /// The difficulty class of the saving throw.
+    dc: i32,
+
+    /// The damage dealt on a failed save, which is empty if the effect deals no damage. A
+    /// successful save takes half damage.
+    damage: Vec<TypedDamage>,
+}
+
+impl Effect {
+    /// Parses an effect entered as `<ability> <dc> [damage]`, e.g. `dex 15 8d6 fire`.
+    fn parse(input: &str) -> Result<Effect, String> {
+        let mut words = input.split_whitespace();
+        let ability = words.next()
+            .ok_or_else(|| "expected an ability, DC and damage, e.g. dex 15 8d6".to_string())?;
+        let ability = Ability::parse(ability).ok_or_else(|| format!("unknown ability \"{}\"", ability))?;
+        let dc = words.next()
+            .and_then(|dc| dc.parse().ok())
+            .ok_or_else(|| "expected a DC after the ability, e.g. dex 15".to_string())?;
+        let damage = words.collect::<Vec<_>>().join(" ");
+        let damage = match damage.as_str() {
+            "" => Vec::new(),
+            damage => TypedDamage::parse_all(damage)?,
+        };
+        Ok(Effect { ability, dc, damage })
+    }
+}
+
+/// The outcome of a target's saving throw.
+#[derive(PartialEq, Eq)]
+pub struct SaveResult {
+    /// The saving throw roll, or [`None`] if the target failed automatically.
+    pub roll: Option<Roll>,
+
+    /// Whether the target succeeded.
+    pub success: bool,
+
+    /// The damage the target took, or [`None`] if the effect deals no damage.
+    pub damage: Option<i32>,
+}
+
+/// Rolling a saving throw for several combatants at once, such as against a fireball, and
+/// applying full damage to those who fail and half damage to those who succeed.
+#[derive(Default, PartialEq, Eq)]
+pub struct SavingThrow {
+    /// The indices of the combatants that will roll.
+    pub targets: Vec<usize>,
+
+    /// The index of the currently focused combatant.
+    pub row: usize,
+
+    /// Advantage or disadvantage chosen by the user for each combatant, on top of any from their
+    /// conditions.
+    pub modes: Vec<RollMode>,
+
+    /// The result of the last saving throw of each combatant that rolled it.
+    pub results: Vec<Option<SaveResult>>,
+
+    /// The name and hit points of each combatant, for display.
+    rows: Vec<(String, String)>,
+
+    /// A description of the last effect and its damage roll.
+    pub last_effect: Option<String>,
+
+    /// The error from parsing the last entered effect, if it was invalid.
+    pub error: Option<String>,
+
+    input: Input,
+}
+
+impl SavingThrow {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        "<escape>: back to initiative tracker\n<enter>: roll saving throw for targets, entered as ability, DC and damage (e.g. dex 15 8d6 fire)\n<tab>: toggle focused combatant as target\n<shift-tab>: cycle advantage / disadvantage for focused combatant\n<up>: previous combatant\n<down>: next combatant".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let last = match (&self.error, &self.last_effect) {
+            (Some(error), _) => Some(Row::new([Text::from(""), Text::from("Error"), Text::from(error.as_str())]).red()),
+            (None, Some(effect)) => Some(Row::new([Text::from(""), Text::from("Last"), Text::from(effect.as_str())])),
+            (None, None) => None,
+        };
+        Table::new(
+            self.rows.iter()
+                .enumerate()
+                .map(|(i, (name, hp))| {
+                    let marker = if self.targets.contains(&i) { "[x]" } else { "[ ]" };
+                    let mode = self.modes.get(i).copied().unwrap_or_default().label();
+                    let result = match self.results.get(i) {
+                        Some(Some(result)) => {
+                            let outcome = if result.success { "pass" } else { "fail" };
+                            let roll = match &result.roll {
+                                Some(roll) => roll.to_string(),
+                                None => "automatic".to_string(),
+                            };
+                            let line = match result.damage {
+                                Some(damage) => Line::from(format!("{}, {} damage ({})", outcome, damage, roll)),
+                                None => Line::from(format!("{} ({})", outcome, roll)),
+                            };
+                            if result.success { line.green() } else { line.red() }
+                        },
+                        _ => Line::from(mode),
+                    };
+                    let row = Row::new([
+                        Text::from(marker),
+                        Text::from(format!("{} {}", name, hp)),
+                        Text::from(result),
+                    ]);
+                    if i == self.row {
+                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
+                    } else {
+                        row
+                    }
+                })
+                .chain(last),
+            [Constraint::Length(6), Constraint::Percentage(40), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title("Saving Throw")
+            )
+    }
+
+    pub fn set_row(&mut self, row: usize, tracker: &mut Tracker) {
+        self.row = row;
+        tracker.highlight(row);
+        self.rows = tracker.combatants()
+            .iter()
+            .map(|c| (c.name().to_string(), format!("({} / {})", c.hit_points(), c.max_hit_points())))
+            .collect();
+        self.modes.resize(self.rows.len(), RollMode::Normal);
+    }
+
+    pub fn init_tracker(&mut self, tracker: &mut Tracker) {
+        let row = tracker.highlighted().unwrap_or_default();
+        if tracker.combatant(row).is_some() {
+            self.targets = vec![row];
+        }
+        self.set_row(row, tracker);
+    }
+
+    /// Rolls the effect's saving throw for every target, or for the focused combatant if there
+    /// are no targets, and applies its damage.
+    fn apply(&mut self, effect: Effect, tracker: &mut Tracker) {
+        let targets = super::take_targets(&mut self.targets, self.row, tracker);
+        if targets.is_empty() {
+            return;
+        }
+        let rolls = effect.damage.iter()
+            .map(|damage| damage.roll(tracker.rng(), false))
+            .collect::<Vec<_>>();
+
+        let names = targets.iter()
+            .filter_map(|&idx| tracker.combatant(idx))
+            .map(|c| c.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let save = format!("{} save DC {}", effect.ability.abbreviation(), effect.dc);
+        let description = match rolls.as_slice() {
+            [] => save.clone(),
+            rolls => format!("{}, {}", save, damage::format_rolls(rolls)),
+        };
+        tracker.record(format!("{} for {}", save, names));
+        self.last_effect = Some(description);
+
+        self.results = Vec::new();
+        self.results.resize_with(tracker.combatants().len(), || None);
+        for idx in targets {
+            let Some(combatant) = tracker.combatant(idx) else {
+                continue;
+            };
+            let modifier = combatant.save_modifier(effect.ability);
+            let conditions = combatant.save_mode(effect.ability);
+            let roll = conditions.map(|mode| {
+                let mode = mode.combine(self.modes.get(idx).copied().unwrap_or_default());
+                Expression::d20(modifier, mode).roll(tracker.rng())
+            });
+            let success = roll.as_ref().is_some_and(|roll| roll.total() >= effect.dc);
+            let damage = (!rolls.is_empty()).then(|| {
+                let damage = rolls.iter()
+                    .map(|&(_, instance)| if success { instance.halved() } else { instance })
+                    .collect::<Vec<_>>();
+                tracker.deal_damage(idx, &damage, false)
+            });
+            self.results[idx] = Some(SaveResult { roll, success, damage });
+        }
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        let len = tracker.combatants().len().max(1);
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                match Effect::parse(self.input.as_str()) {
+                    Ok(effect) => {
+                        self.input.take();
+                        self.error = None;
+                        self.apply(effect, tracker);
+                        self.set_row(self.row, tracker);
+                        if tracker.has_concentration_checks() {
+                            return Some(State::ConcentrationCheck(ConcentrationCheck::default()));
+                        }
+                    },
+                    Err(error) => self.error = Some(error),
+                }
+                None
+            },
+            KeyCode::Tab => {
+                if let Some(pos) = self.targets.iter().position(|&idx| idx == self.row) {
+                    self.targets.remove(pos);
+                } else if self.row < tracker.combatants().len() {
+                    self.targets.push(self.row);
+                }
+                None
+            },
+            KeyCode::BackTab => {
+                if let Some(mode) = self.modes.get_mut(self.row) {
+                    *mode = mode.next();
+                }
+                None
+            },
+            KeyCode::Down => {
+                self.set_row((self.row + 1) % len, tracker);
+                None
+            },
+            KeyCode::Up => {
+                self.set_row((self.row + len - 1) % len, tracker);
+                None
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{combatant::{Combatant, CombatantKind}, input::Input, state::{Home, State}, tracker::Tracker};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::PathBuf;
+
+/// Running a Vim-like command, such as saving or loading the encounter.
+#[derive(Default, PartialEq, Eq)]
+pub struct Command {
+    /// The result of the last command that failed, shown to the user.
+    pub message: Option<String>,
+
+    input: Input,
+}
+
+impl Command {
+    /// Returns the [`Input`] widget.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn help(&self) -> String {
+        "<escape>: cancel, back to initiative tracker\n<enter>: run command\n\nw [file]: save encounter\ne <file>: load encounter\nwq [file]: save encounter and quit\nq: quit\nlair: add a combatant for lair actions on initiative count 20\nset [no]removedead: remove dead monsters when the turn passes, instead of greying them out (not saved with the encounter)".to_string()
+    }
+
+    pub fn render(&self, tracker: &Tracker) -> Table<'_> {
+        let file = tracker.path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "none".to_string());
+        Table::new(
+            [
+                Row::new([Text::from("File").bold(), Text::from(file)]),
+                Row::new([
+                    Text::from("Options").bold(),
+                    Text::from(if tracker.remove_dead() { "removedead" } else { "noremovedead" }),
+                ]),
+                Row::new([Text::from("Error").bold(), Text::from(self.message.clone().unwrap_or_default())])
+                    .red(),
+            ],
+            [Constraint::Length(8), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title("Command")
+            )
+    }
+
+    /// Runs the given command, returning the state to transition to if it succeeded.
+    fn run(&mut self, command: &str, tracker: &mut Tracker) -> Result<State, String> {
+        let (name, argument) = command.trim()
+            .split_once(' ')
+            .map(|(name, argument)| (name, Some(argument.trim())))
+            .unwrap_or((command.trim(), None));
+        let path = argument.filter(|argument| !argument.is_empty()).map(PathBuf::from);
+        match name {
+            "w" => {
+                tracker.save(path).map_err(|err| format!("could not save: {}", err))?;
+                Ok(State::Home(Home::default()))
+            },
+            "e" => {
+                let path = path.ok_or("no file name")?;
+                tracker.load(path).map_err(|err| format!("could not load: {}", err))?;
+                Ok(State::Home(Home::default()))
+            },
+            "set" => {
+                match argument {
+                    Some("removedead") => tracker.set_remove_dead(true),
+                    Some("noremovedead") => tracker.set_remove_dead(false),
+                    Some(option) => return Err(format!("unknown option: {}", option)),
+                    None => return Err("no option".to_string()),
+                }
+                Ok(State::Home(Home::default()))
+            },
+            "lair" => {
+                if tracker.combatants().iter().any(|c| c.kind == CombatantKind::Lair) {
+                    return Err("the encounter already has a lair".to_string());
+                }
+                tracker.add_combatant(Combatant::lair());
+                Ok(State::Home(Home::default()))
+            },
+            "q" => Ok(State::Quit),
+            "wq" => {
+                tracker.save(path).map_err(|err| format!("could not save: {}", err))?;
+                Ok(State::Quit)
+            },
+            _ => Err(format!("unknown command: {}", name)),
+        }
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
+        match unhandled_key.code {
+            KeyCode::Esc => Some(State::Home(Home::default())),
+            KeyCode::Enter => {
+                let command = self.input.take();
+                match self.run(&command, tracker) {
+                    Ok(state) => Some(state),
+                    Err(message) => {
+                        self.message = Some(message);
+                        None
+                    },
+                }
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{actions::Action, combatant::Combatant, state::{combatant::death::DeathSave, State}, tracker::Tracker};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+
+/// The number of lines scrolled by a half-page scroll of the stat block.
+const HALF_PAGE: u16 = 5;
+
+/// The home state, where the user can view the initiative order, move the highlight between
+/// combatants, and launch any other state.
+#[derive(Default, PartialEq, Eq)]
+pub struct Home {
+    /// The first key of a two-key command, such as `gg`, if it has been pressed.
+    pub pending: Option<char>,
+
+    /// How many lines the stat block of the highlighted combatant is scrolled down.
+    pub scroll: u16,
+}
+
+impl Home {
+    /// Returns the help message for the commands of the home state that do not transition to
+    /// another state.
+    pub fn help(&self, tracker: &Tracker) -> String {
+        let mut help = "j / k / <down> / <up>: highlight next / previous combatant\ngg / G: highlight first / last combatant\nn: next turn (dying characters roll a death saving throw)\np: previous turn\nM / A / B / R: spend move / action / bonus action / reaction\nH: spend limited action (e.g. from Haste)\nL: spend a legendary action, at the end of another combatant's turn\n+: grant an extra action until the start of their next turn (e.g. Action Surge)\nK / J: move up / down among tied initiatives\nD: duplicate highlighted combatant".to_string();
+        if tracker.highlighted().and_then(|idx| tracker.combatant(idx)?.stat_block.as_ref()).is_some() {
+            help.push_str("\n<ctrl-e> / <ctrl-y>: scroll stat block down / up\n<ctrl-d> / <ctrl-u>: scroll stat block down / up half a page");
+        }
+        if let Some(description) = tracker.history().next_undo() {
+            help.push_str(&format!("\nu: undo {}", description));
+        }
+        if let Some(description) = tracker.history().next_redo() {
+            help.push_str(&format!("\n<ctrl-r>: redo {}", description));
+        }
+        help
+    }
+
+    /// Renders the stat block of the highlighted combatant, if it has one.
+    pub fn render<'a>(&self, tracker: &'a Tracker) -> Option<Paragraph<'a>> {
+        let combatant = tracker.combatant(tracker.highlighted()?)?;
+        let stat_block = combatant.stat_block.as_ref()?;
+        Some(
+            Paragraph::new(stat_block.text())
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0))
+                .block(
+                    Block::bordered()
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(Color::White))
+                        .padding(Padding::horizontal(1))
+                        .title(format!("Stat Block - {}", combatant.name()))
+                )
+        )
+    }
+
+    /// Scrolls the stat block of the highlighted combatant by the given number of lines, stopping
+    /// at the top and at the last line.
+    fn scroll(&mut self, lines: i32, tracker: &Tracker) {
+        let len = tracker.highlighted()
+            .and_then(|idx| tracker.combatant(idx)?.stat_block.as_ref())
+            .map(|stat_block| stat_block.text().height())
+            .unwrap_or_default();
+        let max = u16::try_from(len.saturating_sub(1)).unwrap_or(u16::MAX);
+        self.scroll = (i32::from(self.scroll) + lines).clamp(0, i32::from(max)) as u16;
+    }
+
+    /// Highlights the combatant the given number of rows away from the highlighted combatant,
+    /// stopping at the first and last combatants.
+    fn move_highlight(&self, rows: isize, tracker: &mut Tracker) {
+        let len = tracker.combatants().len();
+        if len == 0 {
+            return;
+        }
+        let idx = match tracker.highlighted() {
+            Some(idx) => idx.saturating_add_signed(rows).min(len - 1),
+            None => tracker.turn().unwrap_or_default(),
+        };
+        tracker.highlight(idx);
+    }
+
+    /// Receive events from the keyboard that were not handled by a transition.
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let pending = self.pending.take();
+        let highlighted = tracker.highlighted();
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('r') if control => {
+                tracker.redo();
+            },
+            KeyCode::Char('e') if control => self.scroll(1, tracker),
+            KeyCode::Char('y') if control => self.scroll(-1, tracker),
+            KeyCode::Char('d') if control => self.scroll(HALF_PAGE.into(), tracker),
+            KeyCode::Char('u') if control => self.scroll(-i32::from(HALF_PAGE), tracker),
+            KeyCode::Char('u') => {
+                tracker.undo();
+            },
+            KeyCode::Char('j') | KeyCode::Down => self.move_highlight(1, tracker),
+            KeyCode::Char('k') | KeyCode::Up => self.move_highlight(-1, tracker),
+            KeyCode::Char('g') if pending == Some('g') && !tracker.combatants().is_empty() => {
+                tracker.highlight(0);
+            },
+            KeyCode::Char('g') => self.pending = Some('g'),
+            KeyCode::Char('G') => {
+                if let Some(last) = tracker.combatants().len().checked_sub(1) {
+                    tracker.highlight(last);
+                }
+            },
+            KeyCode::Char('n') => {
+                tracker.next_turn();
+                // a dying character makes a death saving throw at the start of their turn
+                if tracker.turn().and_then(|turn| tracker.combatant(turn)).is_some_and(Combatant::is_dying) {
+                    return Some(State::DeathSave(DeathSave::default()));
+                }
+            },
+            KeyCode::Char('p') => tracker.previous_turn(),
+            KeyCode::Char(c @ ('K' | 'J')) => {
+                let idx = tracker.highlighted()?;
+                tracker.move_in_ties(idx, c == 'K');
+            },
+            KeyCode::Char('D') => {
+                let idx = tracker.highlighted()?;
+                tracker.duplicate_combatant(idx);
+            },
+            KeyCode::Char(c @ ('M' | 'A' | 'B' | 'R' | 'H')) => {
+                let action = match c {
+                    'M' => Action::Move,
+                    'A' => Action::Action,
+                    'B' => Action::BonusAction,
+                    'H' => Action::Limited,
+                    _ => Action::Reaction,
+                };
+                let idx = tracker.highlighted()?;
+                let combatant = tracker.combatant(idx)?;
+                if combatant.has_action(action) {
+                    tracker.record(format!("{} spends {}", combatant.name(), action.name()));
+                    tracker.combatant_mut(idx)?.spend_action(action);
+                }
+            },
+            KeyCode::Char('+') => {
+                let idx = tracker.highlighted()?;
+                tracker.record(format!("{} gains an extra action", tracker.combatant(idx)?.name()));
+                tracker.combatant_mut(idx)?.grant_action(Action::Action);
+            },
+            KeyCode::Char('L') => {
+                tracker.spend_legendary_action(tracker.highlighted()?);
+            },
+            _ => (),
+        }
+
+        // a different combatant's stat block starts at the top
+        if tracker.highlighted() != highlighted {
+            self.scroll = 0;
+        }
+        None
+    }
+}
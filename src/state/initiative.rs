@@ -1,15 +1,30 @@
-use crate::{combatant::Combatant, input::Input, state::State, tracker::Tracker};
+use crate::{
+    combatant::CombatantKind,
+    dice::{self, ParseError, Roll},
+    input::Input,
+    state::{Home, State},
+    tracker::Tracker,
+};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
+use std::collections::HashMap;
 
 /// Roll initiative for a combat encounter.
 #[derive(Default, PartialEq, Eq)]
 pub struct RollInitiative {
+    /// Whether initiative is rolled automatically for monsters, so that only player characters are
+    /// prompted for their initiative.
+    pub auto: bool,
+
     /// The name of the combatant we're rolling initiative for.
     pub name: Option<String>,
 
-    /// The initiative roll of the combatant.
-    pub initiative: Option<i32>,
+    /// The initiative rolls made so far, and the names of the combatants they were rolled for.
+    /// The most recent roll is last.
+    pub rolls: Vec<(String, Roll)>,
+
+    /// The error from parsing the last entered initiative, if it was invalid.
+    pub error: Option<ParseError>,
 
     /// The index of the currently focused combatant.
     pub row: usize,
@@ -18,21 +33,39 @@ pub struct RollInitiative {
 }
 
 impl RollInitiative {
+    /// Creates the state in automatic mode, where initiative is rolled for every monster that
+    /// hasn't rolled yet and only player characters are prompted.
+    pub fn auto() -> RollInitiative {
+        RollInitiative { auto: true, ..Default::default() }
+    }
+
     /// Returns the [`Input`] widget.
     pub fn input(&self) -> &Input {
         &self.input
     }
 
     pub fn help(&self) -> String {
-        "<escape>: cancel, back to initiative tracker\n<enter>: set initiative\n<ctrl-enter>: set initiative and finish, sort combatants\n<up>: previous combatant\n<down>: next combatant".to_string()
+        "<escape>: back to initiative tracker, sort combatants\n<enter>: set initiative (number or dice, e.g. 1d20+2)\n<ctrl-enter>: set initiative and finish, sort combatants\n<up>: previous combatant\n<down>: next combatant".to_string()
     }
 
-    pub fn render(&self) -> Table {
+    pub fn render(&self) -> Table<'_> {
+        let title = match &self.name {
+            Some(name) => format!("Rolling Initiative for {}", name),
+            None if self.auto => "Initiative rolled, <ctrl-enter> to finish".to_string(),
+            None => "Rolling Initiative for Unknown".to_string(),
+        };
         Table::new(
             [
                 Row::new([Text::from("Initiative").bold(), Text::from(self.input.as_str())])
-                    .style(Style::default().bg(Color::Rgb(0, 48, 130)))
-            ],
+                    .style(Style::default().bg(Color::Rgb(0, 48, 130))),
+            ]
+                .into_iter()
+                .chain(self.error.as_ref().map(|error| {
+                    Row::new([Text::from("Error").bold(), Text::from(error.to_string())]).red()
+                }))
+                .chain(self.rolls.iter().rev().map(|(name, roll)| {
+                    Row::new([Text::from(name.as_str()).bold(), Text::from(roll.to_string())])
+                })),
             [Constraint::Percentage(50), Constraint::Percentage(50)],
         )
             .block(
@@ -40,7 +73,7 @@ impl RollInitiative {
                     .border_type(BorderType::Rounded)
                     .border_style(Style::default().fg(Color::White))
                     .padding(Padding::horizontal(1))
-                    .title(format!("Rolling Initiative for {}", self.name.as_deref().unwrap_or_else(|| "Unknown")))
+                    .title(title)
             )
     }
 
@@ -50,44 +83,228 @@ impl RollInitiative {
         self.name = tracker.combatant(row).map(|c| c.name().to_string());
     }
 
+    /// Returns true if the combatant at the given index should be prompted for initiative.
+    ///
+    /// Only the first combatant of a group that shares initiative is prompted, and the initiative
+    /// entered for it is given to the whole group.
+    fn prompts(&self, idx: usize, tracker: &Tracker) -> bool {
+        let Some(combatant) = tracker.combatant(idx) else {
+            return false;
+        };
+        let first_of_group = combatant.group.is_none_or(|group| {
+            !tracker.combatants()[..idx].iter().any(|c| c.group == Some(group))
+        });
+        // the lair always acts on initiative count 20
+        first_of_group && combatant.kind != CombatantKind::Lair && (!self.auto || combatant.kind == CombatantKind::Player)
+    }
+
+    /// Returns the index of the next combatant to prompt for initiative after the given one, in
+    /// the given direction (1 for forwards, -1 for backwards), wrapping around.
+    fn next_row(&self, from: usize, direction: isize, tracker: &Tracker) -> Option<usize> {
+        let len = tracker.combatants().len();
+        (1..=len)
+            .map(|offset| (from as isize + direction * offset as isize).rem_euclid(len as isize) as usize)
+            .find(|&idx| self.prompts(idx, tracker))
+    }
+
+    /// Focuses the next combatant to prompt in the given direction, if any.
+    fn move_row(&mut self, direction: isize, tracker: &mut Tracker) {
+        if let Some(row) = self.next_row(self.row, direction, tracker) {
+            self.set_row(row, tracker);
+        }
+    }
+
     pub fn init_tracker(&mut self, tracker: &mut Tracker) {
-        self.set_row(0, tracker);
+        if self.auto {
+            self.roll_monsters(tracker);
+        }
+        match (0..tracker.combatants().len()).find(|&idx| self.prompts(idx, tracker)) {
+            Some(row) => self.set_row(row, tracker),
+            None => tracker.unhighlight(),
+        }
     }
 
-    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
-        let Some(unhandled_key) = self.input.update(key) else {
-            return None;
+    /// Rolls initiative for every monster that hasn't rolled yet, using their initiative modifier
+    /// and advantage. A group that shares initiative rolls once, using the first monster of the
+    /// group.
+    ///
+    /// Monsters are added with an initiative of 0 unless one is entered, so monsters that are
+    /// already in the fight keep their initiative when reinforcements roll.
+    fn roll_monsters(&mut self, tracker: &mut Tracker) {
+        let monsters = tracker.combatants()
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.kind == CombatantKind::Monster && c.initiative() == 0)
+            .map(|(idx, c)| (idx, c.group, c.name().to_string(), c.initiative_expression()))
+            .collect::<Vec<_>>();
+        if monsters.is_empty() {
+            return;
+        }
+
+        tracker.record("roll initiative for monsters");
+        let mut group_rolls = HashMap::new();
+        for (idx, group, name, expression) in monsters {
+            let initiative = match group.and_then(|group| group_rolls.get(&group)) {
+                Some(&initiative) => initiative,
+                None => {
+                    let roll = expression.roll(tracker.rng());
+                    let initiative = roll.total();
+                    if let Some(group) = group {
+                        group_rolls.insert(group, initiative);
+                    }
+                    self.rolls.push((name, roll));
+                    initiative
+                },
+            };
+            if let Some(combatant) = tracker.combatant_mut(idx) {
+                combatant.set_initiative(initiative);
+            }
+        }
+    }
+
+    /// Rolls the entered initiative and sets it for the focused combatant.
+    ///
+    /// Returns false if the entered initiative was invalid.
+    fn set_initiative(&mut self, tracker: &mut Tracker) -> bool {
+        let Some(name) = self.name.clone() else {
+            return true;
         };
+        let roll = match dice::roll(self.input.as_str(), tracker.rng()) {
+            Ok(roll) => roll,
+            Err(error) => {
+                self.error = Some(error);
+                return false;
+            },
+        };
+        self.input.take();
+        self.error = None;
+
+        tracker.record(format!("set initiative of {}", name));
+        let group = tracker.combatant(self.row).and_then(|c| c.group);
+        for idx in 0..tracker.combatants().len() {
+            let Some(combatant) = tracker.combatant_mut(idx) else {
+                continue;
+            };
+            if idx == self.row || (group.is_some() && combatant.group == group) {
+                combatant.set_initiative(roll.total());
+            }
+        }
+        self.rolls.push((name, roll));
+        true
+    }
+
+    /// Sorts the combatants by initiative, starting combat if it hasn't started yet. Initiative
+    /// rolled mid-fight, such as for reinforcements, keeps the current turn and round.
+    fn finish(&mut self, tracker: &mut Tracker) -> Option<State> {
+        if tracker.turn().is_some() {
+            tracker.record("sort combatants");
+            tracker.sort();
+        } else {
+            tracker.start_combat();
+        }
+
+        Some(State::Home(Home::default()))
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        let unhandled_key = self.input.update(key)?;
         match unhandled_key.code {
-            KeyCode::Esc => Some(State::Home),
+            KeyCode::Esc => {
+                // keep the initiative order consistent with the initiative rolled so far
+                if !self.rolls.is_empty() {
+                    tracker.record("sort combatants");
+                    tracker.sort();
+                }
+
+                Some(State::Home(Home::default()))
+            },
+            KeyCode::Enter if self.auto => {
+                if !self.set_initiative(tracker) {
+                    return None;
+                }
+
+                // in automatic mode, finish once every player character has rolled
+                match self.next_row(self.row, 1, tracker).filter(|&row| row > self.row) {
+                    Some(row) => {
+                        self.set_row(row, tracker);
+                        None
+                    },
+                    None => self.finish(tracker),
+                }
+            },
             KeyCode::Enter => {
-                let initiative = self.input.take().parse().ok().unwrap_or_default();
-                tracker.combatant_mut(self.row).map(|c| c.initiative = initiative);
+                if !self.set_initiative(tracker) {
+                    return None;
+                }
 
-                let next_row = (self.row + 1) % tracker.combatants().len();
-                self.set_row(next_row, tracker);
+                self.move_row(1, tracker);
                 None
             },
             KeyCode::Down => {
-                let next_row = (self.row + 1) % tracker.combatants().len();
-                self.set_row(next_row, tracker);
+                self.move_row(1, tracker);
                 None
             },
             KeyCode::Up => {
-                let prev_row = self.row.wrapping_sub(1) % tracker.combatants().len();
-                self.set_row(prev_row, tracker);
+                self.move_row(-1, tracker);
                 None
             },
             // enter seems to return ctrl-j
             KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                let initiative = self.input.take().parse().ok().unwrap_or_default();
-                tracker.combatant_mut(self.row).map(|c| c.initiative = initiative);
+                if !self.input.is_empty() && !self.set_initiative(tracker) {
+                    return None;
+                }
 
-                tracker.sort();
-
-                Some(State::Home)
+                self.finish(tracker)
             },
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combatant::Combatant;
+
+    fn monster(name: &str, initiative: i32) -> Combatant {
+        let mut combatant = Combatant::new(name.to_string(), 7, 7);
+        combatant.kind = CombatantKind::Monster;
+        combatant.set_initiative(initiative);
+        combatant
+    }
+
+    #[test]
+    fn auto_roll_keeps_initiative_already_rolled() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(monster("Goblin 1", 25));
+        tracker.start_combat();
+        tracker.add_combatant(monster("Goblin 2", 0));
+
+        let mut roll = RollInitiative::auto();
+        roll.init_tracker(&mut tracker);
+        assert_eq!(roll.rolls.len(), 1);
+        assert_eq!(roll.rolls[0].0, "Goblin 2");
+        let goblin = tracker.combatants().iter().find(|c| c.name() == "Goblin 1").unwrap();
+        assert_eq!(goblin.initiative(), 25);
+    }
+
+    #[test]
+    fn escape_sorts_rolled_initiative() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(monster("Goblin", 0));
+        tracker.add_combatant(monster("Wolf", 0));
+
+        let mut roll = RollInitiative::default();
+        roll.init_tracker(&mut tracker);
+        let first = roll.name.clone().unwrap();
+        roll.input.set("1".to_string());
+        roll.handle_event(KeyCode::Enter.into(), &mut tracker);
+        roll.input.set("30".to_string());
+        roll.handle_event(KeyCode::Enter.into(), &mut tracker);
+        assert!(roll.handle_event(KeyCode::Esc.into(), &mut tracker).is_some());
+
+        let names = tracker.combatants().iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names.last(), Some(&first.as_str()));
+        assert_eq!(tracker.turn(), None);
+    }
+}
@@ -1,11 +1,28 @@
 mod combatant;
+mod command;
+mod home;
 mod initiative;
+mod recover;
 
-use combatant::add::AddCombatant;
+use combatant::{
+    add::AddCombatant,
+    attack::Attack,
+    check::ConcentrationCheck,
+    concentration::Concentrate,
+    conditions::Conditions,
+    damage::Damage,
+    death::DeathSave,
+    remove::RemoveCombatant,
+    resources::Resources,
+    save::SavingThrow,
+};
+use command::Command;
 use crate::{input::Input, tracker::Tracker};
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use initiative::RollInitiative;
 use ratatui::{prelude::*, widgets::*};
+pub use home::Home;
+pub use recover::Recover;
 
 fn fmt_key_code(key: KeyCode) -> String {
     match key {
@@ -34,8 +51,11 @@ impl From<State> for Transition {
 }
 
 /// A widget that can be rendered to the terminal.
+#[allow(clippy::large_enum_variant)]
 pub enum AnyWidget<'a> {
     Table(Table<'a>),
+    ScrolledTable(Table<'a>, TableState),
+    Paragraph(Paragraph<'a>),
     Input(&'a Input),
 }
 
@@ -45,6 +65,8 @@ impl Widget for AnyWidget<'_> {
     {
         match self {
             AnyWidget::Table(table) => Widget::render(table, area, buf),
+            AnyWidget::ScrolledTable(table, mut state) => StatefulWidget::render(table, area, buf, &mut state),
+            AnyWidget::Paragraph(paragraph) => Widget::render(paragraph, area, buf),
             AnyWidget::Input(input) => Widget::render(input, area, buf),
         }
     }
@@ -56,6 +78,19 @@ impl<'a> From<Table<'a>> for AnyWidget<'a> {
     }
 }
 
+/// Creates a table that is scrolled to show its selected row.
+impl<'a> From<(Table<'a>, TableState)> for AnyWidget<'a> {
+    fn from((table, state): (Table<'a>, TableState)) -> Self {
+        AnyWidget::ScrolledTable(table, state)
+    }
+}
+
+impl<'a> From<Paragraph<'a>> for AnyWidget<'a> {
+    fn from(paragraph: Paragraph<'a>) -> Self {
+        AnyWidget::Paragraph(paragraph)
+    }
+}
+
 impl<'a> From<&'a Input> for AnyWidget<'a> {
     fn from(input: &'a Input) -> Self {
         AnyWidget::Input(input)
@@ -63,19 +98,51 @@ impl<'a> From<&'a Input> for AnyWidget<'a> {
 }
 
 /// Any state the initiative tracker can be in.
-#[derive(Default, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
 pub enum State {
     /// The home state, where the user can view the initiative order and launch any other state
     /// below.
-    #[default]
-    Home,
+    Home(Home),
 
-    /// Adding a new combatant to the initiative order.
+    /// Adding a new combatant to the initiative order, or editing an existing one.
     AddCombatant(AddCombatant),
 
+    /// Removing the highlighted combatant from the initiative order.
+    RemoveCombatant(RemoveCombatant),
+
     /// Roll initiative for all combatants.
     RollInitiative(RollInitiative),
 
+    /// Dealing damage to, or healing, one or more combatants.
+    Damage(Damage),
+
+    /// Rolling an attack from one combatant against another, and applying the damage.
+    Attack(Attack),
+
+    /// Rolling a saving throw for one or more combatants, and applying the damage.
+    SavingThrow(SavingThrow),
+
+    /// Adding and removing conditions on a combatant.
+    Conditions(Conditions),
+
+    /// Recording a death saving throw for a dying player character.
+    DeathSave(DeathSave),
+
+    /// Starting or ending a combatant's concentration on a spell.
+    Concentrate(Concentrate),
+
+    /// Rolling the saving throw for a damaged combatant to keep concentrating.
+    ConcentrationCheck(ConcentrationCheck),
+
+    /// Spending and restoring a combatant's spell slots and other limited-use resources.
+    Resources(Resources),
+
+    /// Running a command, such as saving or loading the encounter.
+    Command(Command),
+
+    /// Offering to resume an unfinished encounter on startup.
+    Recover(Recover),
+
     /// Special quit state, which exits the program.
     Quit,
 }
@@ -88,22 +155,65 @@ impl State {
     /// A transition declaration can also override the default key that triggers the transition.
     pub fn transitions(&self) -> Vec<Transition> {
         match self {
-            State::Home => vec![
+            State::Home(_) => vec![
                 State::AddCombatant(AddCombatant::default()).into(),
+                Transition {
+                    state: State::AddCombatant(AddCombatant::edit()),
+                    key: KeyCode::Char('e'),
+                },
+                State::RemoveCombatant(RemoveCombatant::default()).into(),
                 State::RollInitiative(RollInitiative::default()).into(),
+                Transition {
+                    state: State::RollInitiative(RollInitiative::auto()),
+                    key: KeyCode::Char('i'),
+                },
+                State::Damage(Damage::default()).into(),
+                State::Attack(Attack::default()).into(),
+                State::SavingThrow(SavingThrow::default()).into(),
+                State::Conditions(Conditions::default()).into(),
+                State::Concentrate(Concentrate::default()).into(),
+                State::Resources(Resources::default()).into(),
+                State::Command(Command::default()).into(),
                 State::Quit.into(),
             ],
-            State::AddCombatant(_) => vec![State::Home.into()],
-            State::RollInitiative(_) => vec![State::Home.into()],
+            State::AddCombatant(_) => vec![State::Home(Home::default()).into()],
+            State::RemoveCombatant(_) => vec![State::Home(Home::default()).into()],
+            State::RollInitiative(_) => vec![State::Home(Home::default()).into()],
+            State::Damage(_) => vec![State::Home(Home::default()).into()],
+            State::Attack(_) => vec![State::Home(Home::default()).into()],
+            State::SavingThrow(_) => vec![State::Home(Home::default()).into()],
+            State::Conditions(_) => vec![State::Home(Home::default()).into()],
+            State::DeathSave(_) => vec![State::Home(Home::default()).into()],
+            State::Concentrate(_) => vec![State::Home(Home::default()).into()],
+            State::ConcentrationCheck(_) => vec![],
+            State::Resources(_) => vec![State::Home(Home::default()).into()],
+            State::Command(_) => vec![State::Home(Home::default()).into()],
+            State::Recover(_) => vec![],
             State::Quit => vec![],
         }
     }
 
     /// Returns the state to transition to given a key event.
-    pub fn transition(&self, key: KeyCode) -> Option<Transition> {
+    ///
+    /// Keys pressed with the control modifier never trigger a transition.
+    pub fn transition(&self, key: KeyEvent) -> Option<Transition> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+
         self.transitions()
             .into_iter()
-            .find(|transition| transition.key == key)
+            .find(|transition| transition.key == key.code)
+    }
+
+    /// Returns false if this state can't be entered with the tracker as it is, such as editing
+    /// when no combatant is highlighted.
+    pub fn can_enter(&self, tracker: &Tracker) -> bool {
+        match self {
+            State::AddCombatant(add) if add.edit => tracker.highlighted()
+                .is_some_and(|idx| idx < tracker.combatants().len()),
+            _ => true,
+        }
     }
 
     /// Returns the default key that triggers this state from any other state.
@@ -111,9 +221,20 @@ impl State {
     /// This can be overriden by other states to provide custom behavior.
     pub fn default_key_event(&self) -> KeyCode {
         match self {
-            State::Home => KeyCode::Char('h'),
+            State::Home(_) => KeyCode::Char('h'),
             State::AddCombatant(_) => KeyCode::Char('a'),
+            State::RemoveCombatant(_) => KeyCode::Char('x'),
             State::RollInitiative(_) => KeyCode::Char('r'),
+            State::Damage(_) => KeyCode::Char('d'),
+            State::Attack(_) => KeyCode::Char('t'),
+            State::SavingThrow(_) => KeyCode::Char('s'),
+            State::Conditions(_) => KeyCode::Char('c'),
+            State::DeathSave(_) => KeyCode::Null,
+            State::Concentrate(_) => KeyCode::Char('o'),
+            State::ConcentrationCheck(_) => KeyCode::Null,
+            State::Resources(_) => KeyCode::Char('l'),
+            State::Command(_) => KeyCode::Char(':'),
+            State::Recover(_) => KeyCode::Null,
             State::Quit => KeyCode::Char('q'),
         }
     }
@@ -121,9 +242,22 @@ impl State {
     /// Returns the description of the state.
     pub fn description(&self) -> &'static str {
         match self {
-            State::Home => "back to initiative tracker",
+            State::Home(_) => "back to initiative tracker",
+            State::AddCombatant(add) if add.edit => "edit highlighted combatant",
             State::AddCombatant(_) => "add combatant to initiative order",
+            State::RemoveCombatant(_) => "remove highlighted combatant",
+            State::RollInitiative(roll) if roll.auto => "roll initiative for monsters, then players",
             State::RollInitiative(_) => "roll initiative!",
+            State::Damage(_) => "damage or heal combatants",
+            State::Attack(_) => "attack a target, rolling against their armor class",
+            State::SavingThrow(_) => "roll a saving throw for combatants, e.g. against a fireball",
+            State::Conditions(_) => "add or remove conditions",
+            State::DeathSave(_) => "roll a death saving throw",
+            State::Concentrate(_) => "start or stop concentrating on a spell",
+            State::ConcentrationCheck(_) => "roll a saving throw to keep concentrating",
+            State::Resources(_) => "spend or restore spell slots and limited-use abilities",
+            State::Command(_) => "run a command (save, load, etc.)",
+            State::Recover(_) => "resume an unfinished encounter",
             State::Quit => "quit the program",
         }
     }
@@ -144,36 +278,73 @@ impl State {
     }
 
     /// Returns the help message for the state.
-    pub fn help(&self) -> String {
+    pub fn help(&self, tracker: &Tracker) -> String {
         match self {
             State::AddCombatant(add) => add.help(),
+            State::RemoveCombatant(remove) => remove.help(),
             State::RollInitiative(roll) => roll.help(),
+            State::Damage(damage) => damage.help(),
+            State::Attack(attack) => attack.help(),
+            State::SavingThrow(save) => save.help(),
+            State::Conditions(conditions) => conditions.help(),
+            State::DeathSave(death_save) => death_save.help(),
+            State::Concentrate(concentrate) => concentrate.help(),
+            State::ConcentrationCheck(check) => check.help(),
+            State::Resources(resources) => resources.help(),
+            State::Command(command) => command.help(),
+            State::Recover(recover) => recover.help(),
+            State::Home(home) => format!("{}\n{}", self.default_help(), home.help(tracker)),
             _ => self.default_help(),
         }
     }
 
     /// Renders the state to two widgets, one for the state, and one for the input.
-    pub fn render(&self) -> Option<(AnyWidget, Option<AnyWidget>)> {
+    pub fn render<'a>(&'a self, tracker: &'a Tracker) -> Option<(AnyWidget<'a>, Option<AnyWidget<'a>>)> {
         match self {
-            State::AddCombatant(add) => Some((add.render().into(), Some(add.input().into()))),
+            State::AddCombatant(add) => Some((add.render(tracker).into(), Some(add.input().into()))),
+            State::RemoveCombatant(remove) => Some((remove.render().into(), None)),
             State::RollInitiative(roll) => Some((roll.render().into(), Some(roll.input().into()))),
+            State::Damage(damage) => Some((damage.render().into(), Some(damage.input().into()))),
+            State::Attack(attack) => Some((attack.render().into(), Some(attack.input().into()))),
+            State::SavingThrow(save) => Some((save.render().into(), Some(save.input().into()))),
+            State::Conditions(conditions) => Some((conditions.render().into(), Some(conditions.input().into()))),
+            State::DeathSave(death_save) => Some((death_save.render().into(), Some(death_save.input().into()))),
+            State::Concentrate(concentrate) => Some((concentrate.render().into(), Some(concentrate.input().into()))),
+            State::ConcentrationCheck(check) => Some((check.render().into(), Some(check.input().into()))),
+            State::Resources(resources) => Some((resources.render().into(), Some(resources.input().into()))),
+            State::Command(command) => Some((command.render(tracker).into(), Some(command.input().into()))),
+            State::Recover(recover) => Some((recover.render().into(), None)),
+            State::Home(home) => home.render(tracker).map(|paragraph| (paragraph.into(), None)),
             _ => None,
         }
     }
 
     /// Returns true if this state needs to handle keyboard events.
     pub fn needs_keyboard(&self) -> bool {
-        match self {
-            State::AddCombatant(_) => true,
-            State::RollInitiative(_) => true,
-            _ => false,
-        }
+        matches!(self, State::AddCombatant(_) | State::RemoveCombatant(_) | State::RollInitiative(_) | State::Damage(_) | State::Attack(_) | State::SavingThrow(_) | State::Conditions(_) | State::DeathSave(_) | State::Concentrate(_) | State::ConcentrationCheck(_) | State::Resources(_) | State::Command(_) | State::Recover(_))
     }
 
     /// Initialize the tracker when transitioning to this state, if necessary.
     pub fn init_tracker(&mut self, tracker: &mut Tracker) {
         match self {
+            // keep the highlighted combatant, so commands can be repeated on the same combatant
+            State::Home(_) => match (tracker.highlighted(), tracker.turn()) {
+                (Some(idx), _) if idx < tracker.combatants().len() => (),
+                (_, Some(turn)) => tracker.highlight(turn),
+                _ if !tracker.combatants().is_empty() => tracker.highlight(0),
+                _ => tracker.unhighlight(),
+            },
+            State::AddCombatant(add) => add.init_tracker(tracker),
+            State::RemoveCombatant(remove) => remove.init_tracker(tracker),
             State::RollInitiative(roll) => roll.init_tracker(tracker),
+            State::Damage(damage) => damage.init_tracker(tracker),
+            State::Attack(attack) => attack.init_tracker(tracker),
+            State::SavingThrow(save) => save.init_tracker(tracker),
+            State::Conditions(conditions) => conditions.init_tracker(tracker),
+            State::DeathSave(death_save) => death_save.init_tracker(tracker),
+            State::Concentrate(concentrate) => concentrate.init_tracker(tracker),
+            State::ConcentrationCheck(check) => check.init_tracker(tracker),
+            State::Resources(resources) => resources.init_tracker(tracker),
             _ => (),
         }
     }
@@ -181,8 +352,20 @@ impl State {
     /// Receive events from the keyboard.
     pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
         match self {
+            State::Home(home) => home.handle_event(key, tracker),
             State::AddCombatant(add) => add.handle_event(key, tracker),
+            State::RemoveCombatant(remove) => remove.handle_event(key, tracker),
             State::RollInitiative(roll) => roll.handle_event(key, tracker),
+            State::Damage(damage) => damage.handle_event(key, tracker),
+            State::Attack(attack) => attack.handle_event(key, tracker),
+            State::SavingThrow(save) => save.handle_event(key, tracker),
+            State::Conditions(conditions) => conditions.handle_event(key, tracker),
+            State::DeathSave(death_save) => death_save.handle_event(key, tracker),
+            State::Concentrate(concentrate) => concentrate.handle_event(key, tracker),
+            State::ConcentrationCheck(check) => check.handle_event(key, tracker),
+            State::Resources(resources) => resources.handle_event(key, tracker),
+            State::Command(command) => command.handle_event(key, tracker),
+            State::Recover(recover) => recover.handle_event(key, tracker),
             _ => None,
         }
     }
@@ -0,0 +1,63 @@
+use crate::{recovery::Recovery, state::{Home, State}, tracker::Tracker};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+/// Offering to resume an encounter that was in progress when the program last exited
+/// unexpectedly.
+#[derive(Default)]
+pub struct Recover {
+    /// The encounter to resume.
+    pub recovery: Option<Recovery>,
+}
+
+impl Recover {
+    /// Creates the state, offering to resume the given encounter.
+    pub fn new(recovery: Recovery) -> Recover {
+        Recover { recovery: Some(recovery) }
+    }
+
+    pub fn help(&self) -> String {
+        "y: resume the unfinished encounter\nn: discard it".to_string()
+    }
+
+    pub fn render(&self) -> Table<'_> {
+        let (combatants, round, file) = self.recovery.as_ref()
+            .map(|recovery| (
+                recovery.encounter.combatants.len(),
+                recovery.encounter.round,
+                recovery.file.as_ref().map(|path| path.display().to_string()),
+            ))
+            .unwrap_or_default();
+        Table::new(
+            [
+                Row::new([Text::from("Combatants").bold(), Text::from(combatants.to_string())]),
+                Row::new([Text::from("Round").bold(), Text::from(round.to_string())]),
+                Row::new([Text::from("File").bold(), Text::from(file.unwrap_or_else(|| "none".to_string()))]),
+            ],
+            [Constraint::Length(12), Constraint::Fill(1)],
+        )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::White))
+                    .padding(Padding::horizontal(1))
+                    .title("Resume unfinished encounter?")
+            )
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent, tracker: &mut Tracker) -> Option<State> {
+        match key.code {
+            KeyCode::Char('y') => {
+                if let Some(recovery) = self.recovery.take() {
+                    tracker.open(recovery.encounter, "resume encounter");
+                    if let Some(path) = recovery.file {
+                        tracker.set_path(path);
+                    }
+                }
+                Some(State::Home(Home::default()))
+            },
+            KeyCode::Char('n') | KeyCode::Esc => Some(State::Home(Home::default())),
+            _ => None,
+        }
+    }
+}
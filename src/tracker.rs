@@ -1,19 +1,57 @@
 use ratatui::{prelude::*, widgets::*};
-use super::{Combatant, State};
+use std::{collections::{HashSet, VecDeque}, fs, io, path::{Path, PathBuf}};
+use super::{resource, Combatant, CombatantId, CombatantKind, Compendium, Downed, DamageInstance, DamageModifier, History, Rng, Snapshot};
+
+/// The number of entries kept in the event log.
+const LOG_LENGTH: usize = 200;
 
 /// Manages the initiative tracker.
 #[derive(Default)]
 pub struct Tracker {
-    /// The current state / view of the tracker, indicating what actions the user can take at the
-    /// current moment.
-    state: State,
-
     /// The combatants of the encounter, ordered by initiative. The first combatant in the list is
     /// the combatant with the highest initiative.
     combatants: Vec<Combatant>,
 
-    /// The index of the currently highlighted combatant.
-    highlighted: Option<usize>,
+    /// The selection of the combatant table, which holds the index of the currently highlighted
+    /// combatant and how far the table is scrolled.
+    table_state: TableState,
+
+    /// The index of the combatant whose turn it is, or [`None`] if combat has not started yet.
+    turn: Option<usize>,
+
+    /// The current round of combat, starting at 1. This is 0 if combat has not started yet.
+    round: u32,
+
+    /// The identifier to give to the next combatant added to the tracker.
+    next_id: u64,
+
+    /// The undo / redo history of the encounter.
+    history: History,
+
+    /// The file the encounter was last saved to or loaded from.
+    path: Option<PathBuf>,
+
+    /// A counter that changes every time the encounter changes, used to detect unsaved changes.
+    revision: u64,
+
+    /// The random number generator used for all dice rolls.
+    rng: Rng,
+
+    /// The monster stat blocks that combatants can be created from.
+    compendium: Compendium,
+
+    /// Whether dead monsters are removed from the initiative order when the turn passes, rather
+    /// than staying in it greyed out. This is a setting for the session rather than part of the
+    /// encounter, so it is neither saved nor undone.
+    remove_dead: bool,
+
+    /// The Constitution saving throws that damaged combatants must make to keep concentrating, as
+    /// each combatant's identifier and the DC, in the order the damage was dealt.
+    concentration_checks: VecDeque<(CombatantId, i32)>,
+
+    /// The event log, oldest entry first. This is not part of the encounter, so it is neither
+    /// saved nor undone.
+    log: Vec<String>,
 }
 
 impl Tracker {
@@ -22,19 +60,197 @@ impl Tracker {
         Tracker::default()
     }
 
+    /// Returns the monster compendium.
+    pub fn compendium(&self) -> &Compendium {
+        &self.compendium
+    }
+
+    /// Sets the monster compendium.
+    pub fn set_compendium(&mut self, compendium: Compendium) {
+        self.compendium = compendium;
+    }
+
+    /// Adds an entry to the event log, dropping the oldest entry if the log is full.
+    pub fn log(&mut self, entry: impl Into<String>) {
+        if self.log.len() >= LOG_LENGTH {
+            self.log.remove(0);
+        }
+        self.log.push(entry.into());
+    }
+
+    /// Returns the entries of the event log, oldest entry first.
+    pub fn log_entries(&self) -> &[String] {
+        &self.log
+    }
+
+    /// Returns true if dead monsters are removed from the initiative order when the turn passes.
+    pub fn remove_dead(&self) -> bool {
+        self.remove_dead
+    }
+
+    /// Sets whether dead monsters are removed from the initiative order when the turn passes.
+    pub fn set_remove_dead(&mut self, remove_dead: bool) {
+        self.remove_dead = remove_dead;
+    }
+
     /// Returns a reference to the combatants in the tracker.
     pub fn combatants(&self) -> &[Combatant] {
         &self.combatants
     }
 
     /// Adds a new combatant to the initiative tracker.
-    pub fn add_combatant(&mut self, combatant: Combatant) {
+    pub fn add_combatant(&mut self, mut combatant: Combatant) {
+        self.record(format!("add {}", combatant.name()));
+        combatant.id = CombatantId(self.next_id);
+        self.next_id += 1;
         self.combatants.push(combatant);
 
         // highest initiative first
         self.sort();
     }
 
+    /// Adds a group of combatants to the initiative tracker in one step.
+    ///
+    /// If `shared` is true, the combatants are grouped so that they share a single initiative
+    /// roll when initiative is rolled for monsters.
+    pub fn add_group(&mut self, mut combatants: Vec<Combatant>, shared: bool) {
+        let description = match combatants.as_slice() {
+            [] => return,
+            [combatant] => format!("add {}", combatant.name()),
+            [first, .., last] => format!("add {} to {}", first.name(), last.name()),
+        };
+        self.record(description);
+        let group = (shared && combatants.len() > 1).then_some(CombatantId(self.next_id));
+        for combatant in &mut combatants {
+            combatant.id = CombatantId(self.next_id);
+            combatant.group = group;
+            self.next_id += 1;
+        }
+        self.combatants.extend(combatants);
+        self.sort();
+    }
+
+    /// Removes the combatant at the given index from the initiative tracker.
+    ///
+    /// If it was the combatant's turn, the turn passes to the next combatant. Conditions that
+    /// would have ended at the end of the combatant's turn are removed, since it will never come.
+    pub fn remove_combatant(&mut self, idx: usize) -> Option<Combatant> {
+        let name = self.combatants.get(idx)?.name().to_string();
+        self.record(format!("remove {}", name));
+        let removed = self.combatants.remove(idx);
+        for combatant in &mut self.combatants {
+            combatant.remove_conditions_from(removed.id);
+        }
+
+        let len = self.combatants.len();
+        let adjust = |i: Option<usize>| match i {
+            _ if len == 0 => None,
+            Some(i) if i > idx => Some(i - 1),
+            Some(i) => Some(i.min(len - 1)),
+            None => None,
+        };
+        let their_turn = self.turn == Some(idx);
+        if their_turn && idx == len && len > 0 {
+            // the last combatant in the order was removed on their turn, so the round is over
+            self.turn = Some(0);
+            self.round += 1;
+            self.begin_turn();
+        } else {
+            self.turn = adjust(self.turn);
+            if their_turn && self.turn.is_some() {
+                self.begin_turn();
+            }
+        }
+        self.table_state.select(adjust(self.table_state.selected()));
+        Some(removed)
+    }
+
+    /// Adds a copy of the combatant at the given index to the initiative tracker, with a new name.
+    /// The copy has the same initiative, hit points, and conditions.
+    pub fn duplicate_combatant(&mut self, idx: usize) {
+        let Some(mut copy) = self.combatants.get(idx).cloned() else {
+            return;
+        };
+        copy.set_name(self.next_name(copy.name()));
+        copy.roll_off = None;
+        self.add_combatant(copy);
+    }
+
+    /// Returns the highest suffix used by a combatant named after the given base name, where
+    /// "Goblin" counts as 1, "Goblin 3" as 3, and "Goblin C" as 3.
+    fn highest_suffix(&self, base: &str) -> Option<u32> {
+        self.combatants.iter()
+            .filter_map(|c| {
+                if c.name() == base {
+                    return Some(1);
+                }
+                let (other_base, suffix) = c.name().rsplit_once(' ')?;
+                (other_base == base).then(|| parse_suffix(suffix)).flatten()
+            })
+            .max()
+    }
+
+    /// Returns a name for a new combatant based on the given name that isn't used by any other
+    /// combatant, by numbering it: "Goblin" becomes "Goblin 2", "Goblin 2" becomes "Goblin 3",
+    /// and "Goblin B" becomes "Goblin C".
+    pub fn next_name(&self, name: &str) -> String {
+        let (base, letters) = match name.rsplit_once(' ') {
+            Some((base, suffix)) if parse_suffix(suffix).is_some() => {
+                (base, suffix.parse::<u32>().is_err())
+            },
+            _ => (name, false),
+        };
+        match self.highest_suffix(base) {
+            Some(highest) if letters => format!("{} {}", base, letter_suffix(highest + 1)),
+            Some(highest) => format!("{} {}", base, highest + 1),
+            None => base.to_string(),
+        }
+    }
+
+    /// Returns names for a group of `count` new combatants based on the given name, suffixed
+    /// "Goblin 1" to "Goblin N", or "Goblin A" onwards if `letters` is true. Numbering continues
+    /// after any combatants already named this way.
+    pub fn group_names(&self, name: &str, count: u32, letters: bool) -> Vec<String> {
+        let start = self.highest_suffix(name).unwrap_or_default();
+        (start + 1..=start + count)
+            .map(|n| if letters {
+                format!("{} {}", name, letter_suffix(n))
+            } else {
+                format!("{} {}", name, n)
+            })
+            .collect()
+    }
+
+    /// Replaces the combatant at the given index with an edited version of it, then sorts the
+    /// combatants in case their initiative changed.
+    pub fn replace_combatant(&mut self, idx: usize, mut combatant: Combatant) {
+        let Some(existing) = self.combatants.get(idx) else {
+            return;
+        };
+        let (id, description) = (existing.id, format!("edit {}", existing.name()));
+        self.record(description);
+        combatant.id = id;
+        self.combatants[idx] = combatant;
+        self.sort();
+    }
+
+    /// Spends one of the legendary actions of the combatant at the given index. Legendary actions
+    /// are taken at the end of another creature's turn, so they can't be spent on the combatant's
+    /// own turn.
+    ///
+    /// Returns true if a legendary action was spent.
+    pub fn spend_legendary_action(&mut self, idx: usize) -> bool {
+        if self.turn == Some(idx) {
+            return false;
+        }
+        let has_left = |c: &&Combatant| c.legendary_actions().is_some_and(|legendary| legendary.left() > 0);
+        let Some(combatant) = self.combatants.get(idx).filter(has_left) else {
+            return false;
+        };
+        self.record(format!("{} spends a legendary action", combatant.name()));
+        self.combatants[idx].spend_legendary_action()
+    }
+
     /// Gets reference to the combatant at the given index.
     pub fn combatant(&self, idx: usize) -> Option<&Combatant> {
         self.combatants.get(idx)
@@ -45,40 +261,531 @@ impl Tracker {
         self.combatants.get_mut(idx)
     }
 
-    /// Sorts the combatants by initiative.
+    /// Deals damage to the combatant at the given index, applying their resistances,
+    /// vulnerabilities and immunities to each type of damage, and logs how much they took and
+    /// whether they fell unconscious or died. A critical hit on a dying character counts as two
+    /// failed death saving throws.
+    ///
+    /// Returns the total damage taken. The caller must [`record`](Tracker::record) the change
+    /// first.
+    pub fn deal_damage(&mut self, idx: usize, damage: &[DamageInstance], critical: bool) -> i32 {
+        let Some(combatant) = self.combatants.get_mut(idx) else {
+            return 0;
+        };
+        let mut total: i32 = 0;
+        let parts = damage.iter()
+            .map(|instance| {
+                let modifier = instance.kind
+                    .map_or(DamageModifier::Normal, |kind| combatant.damage_modifier(kind, instance.magical));
+                let taken = DamageInstance { amount: modifier.apply(instance.amount), ..*instance };
+                total = total.saturating_add(taken.amount);
+                let note = match modifier {
+                    DamageModifier::Normal => String::new(),
+                    DamageModifier::Immune => format!(" ({})", modifier.description()),
+                    _ => format!(" ({} from {})", modifier.description(), instance.amount),
+                };
+                (taken, note)
+            })
+            .collect::<Vec<_>>();
+        let downed = combatant.take_damage(total, critical);
+        let entry = match parts.as_slice() {
+            [(taken, note)] => format!("{} takes {} damage{}", combatant.name(), taken, note),
+            _ => format!(
+                "{} takes {} damage: {}",
+                combatant.name(),
+                total,
+                parts.iter().map(|(taken, note)| format!("{}{}", taken, note)).collect::<Vec<_>>().join(", "),
+            ),
+        };
+        let downed = downed.map(|downed| match downed {
+            Downed::Unconscious => format!("{} falls unconscious", combatant.name()),
+            Downed::FailedDeathSave => {
+                format!("{} fails a death saving throw ({} / 3)", combatant.name(), combatant.death_saves().failures)
+            },
+            Downed::Died => format!("{} dies", combatant.name()),
+            Downed::MassiveDamage => format!("{} dies from massive damage", combatant.name()),
+        });
+
+        // concentration ends when the caster drops to 0 hit points, and is tested by any other damage
+        let concentrating = combatant.concentration.as_ref()
+            .map(|concentration| (combatant.id, combatant.name().to_string(), concentration.spell.clone()));
+        self.log(entry);
+        match (downed, concentrating) {
+            (Some(downed), _) => {
+                self.log(downed);
+                self.break_concentration(idx);
+            },
+            (None, Some((id, name, spell))) if total > 0 => {
+                let dc = (total / 2).max(10);
+                self.log(format!("{} must make a DC {} Constitution saving throw to keep concentrating on {}", name, dc, spell));
+                self.concentration_checks.push_back((id, dc));
+            },
+            _ => (),
+        }
+        total
+    }
+
+    /// Ends the concentration of the combatant at the given index, removing the conditions their
+    /// spell maintained from every combatant, and logs it. The caller must
+    /// [`record`](Tracker::record) the change first.
+    pub fn break_concentration(&mut self, idx: usize) {
+        let Some(caster) = self.combatants.get_mut(idx) else {
+            return;
+        };
+        let Some(concentration) = caster.concentration.take() else {
+            return;
+        };
+        let (id, name) = (caster.id, caster.name().to_string());
+        for combatant in &mut self.combatants {
+            combatant.remove_concentration_conditions(id);
+        }
+        self.concentration_checks.retain(|&(check, _)| check != id);
+        self.log(format!("{} stops concentrating on {}", name, concentration.spell));
+    }
+
+    /// Takes the next concentration saving throw that a damaged combatant must make, as their
+    /// index and the DC.
+    pub fn next_concentration_check(&mut self) -> Option<(usize, i32)> {
+        while let Some((id, dc)) = self.concentration_checks.pop_front() {
+            let idx = self.combatants.iter().position(|c| c.id == id && c.concentration.is_some());
+            if let Some(idx) = idx {
+                return Some((idx, dc));
+            }
+        }
+        None
+    }
+
+    /// Returns true if a damaged combatant must make a concentration saving throw.
+    pub fn has_concentration_checks(&self) -> bool {
+        !self.concentration_checks.is_empty()
+    }
+
+    /// Sorts the combatants by initiative, breaking ties as described in
+    /// [`Combatant::cmp_initiative`]. Combatants that are still tied are given a roll-off first.
+    ///
+    /// The combatant whose turn it is and the highlighted combatant are preserved, even if their
+    /// position in the initiative order changes.
     pub fn sort(&mut self) {
-        self.combatants.sort_by(|a, b| b.initiative().cmp(&a.initiative()));
+        self.reset_joined_ties();
+        self.roll_off_ties();
+
+        let mut order = (0..self.combatants.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| self.combatants[a].cmp_initiative(&self.combatants[b]));
+
+        let remap = |idx: Option<usize>| idx.and_then(|idx| order.iter().position(|&i| i == idx));
+        self.turn = remap(self.turn);
+        self.table_state.select(remap(self.table_state.selected()));
+
+        let mut combatants = std::mem::take(&mut self.combatants)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.combatants = order.into_iter()
+            .filter_map(|idx| combatants[idx].take())
+            .collect();
+    }
+
+    /// Forgets the Dungeon Master's chosen order of every tie that another combatant has joined
+    /// since, as the newcomer has no place in it. Those ties are broken by the other rules again.
+    fn reset_joined_ties(&mut self) {
+        let joined = self.combatants.iter()
+            .filter(|c| c.tie_priority == 0 && c.kind != CombatantKind::Lair)
+            .filter(|c| self.combatants.iter().any(|other| other.tie_priority != 0 && other.can_reorder_with(c)))
+            .map(|c| (c.initiative, c.initiative_modifier))
+            .collect::<Vec<_>>();
+        for combatant in &mut self.combatants {
+            if joined.contains(&(combatant.initiative, combatant.initiative_modifier)) {
+                combatant.tie_priority = 0;
+            }
+        }
+    }
+
+    /// Rolls a d20 roll-off for every combatant that is tied with another combatant and doesn't
+    /// have a roll-off yet. Rolls are repeated to avoid matching another tied combatant's roll-off.
+    fn roll_off_ties(&mut self) {
+        for idx in 0..self.combatants.len() {
+            let combatant = &self.combatants[idx];
+            if combatant.roll_off.is_some() {
+                continue;
+            }
+
+            let tied_roll_offs = self.combatants.iter()
+                .enumerate()
+                .filter(|&(other, c)| other != idx && c.ties_with(combatant))
+                .map(|(_, c)| c.roll_off)
+                .collect::<Vec<_>>();
+            if tied_roll_offs.is_empty() {
+                continue;
+            }
+
+            // give up after a few rerolls; the identifier breaks any remaining tie
+            let mut roll_off = self.rng.roll(20);
+            for _ in 0..20 {
+                if !tied_roll_offs.contains(&Some(roll_off)) {
+                    break;
+                }
+                roll_off = self.rng.roll(20);
+            }
+            self.combatants[idx].roll_off = Some(roll_off);
+        }
+    }
+
+    /// Moves the combatant at the given index one place up (or down) in the initiative order,
+    /// swapping places with the adjacent combatant if they have the same initiative. This records
+    /// the Dungeon Master's chosen order for all combatants with that initiative.
+    ///
+    /// Returns false if the combatant could not be moved.
+    pub fn move_in_ties(&mut self, idx: usize, up: bool) -> bool {
+        let Some(other) = (if up { idx.checked_sub(1) } else { Some(idx + 1) }) else {
+            return false;
+        };
+        let (Some(combatant), Some(neighbor)) = (self.combatants.get(idx), self.combatants.get(other)) else {
+            return false;
+        };
+        if !combatant.can_reorder_with(neighbor) {
+            return false;
+        }
+
+        let description = format!("move {} {} in initiative order", combatant.name(), if up { "up" } else { "down" });
+        self.record(description);
+        let combatant = self.combatants[idx].clone();
+        let group = (0..self.combatants.len())
+            .filter(|&i| i == idx || self.combatants[i].can_reorder_with(&combatant))
+            .collect::<Vec<_>>();
+        for (rank, &i) in group.iter().enumerate() {
+            self.combatants[i].tie_priority = (group.len() - rank) as i32;
+        }
+        let priority = self.combatants[idx].tie_priority;
+        self.combatants[idx].tie_priority = self.combatants[other].tie_priority;
+        self.combatants[other].tie_priority = priority;
+
+        self.sort();
+        true
+    }
+
+    /// Returns a snapshot of the current state of the encounter.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            combatants: self.combatants.clone(),
+            turn: self.turn,
+            round: self.round,
+        }
+    }
+
+    /// Restores the encounter to the given snapshot.
+    ///
+    /// Pending concentration saving throws are dropped, as the damage that called for them is no
+    /// longer part of the encounter.
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.combatants = snapshot.combatants;
+        self.turn = snapshot.turn;
+        self.round = snapshot.round;
+        self.concentration_checks.clear();
+        let highlighted = self.table_state.selected()
+            .filter(|&idx| idx < self.combatants.len())
+            .or(self.turn);
+        self.table_state.select(highlighted);
+    }
+
+    /// Returns the file the encounter was last saved to or loaded from.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Sets the file the encounter will be saved to by default.
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    /// Saves the encounter to the given file, or to the file it was last saved to or loaded from
+    /// if no file is given.
+    ///
+    /// Returns the path of the file that was written.
+    pub fn save(&mut self, path: Option<PathBuf>) -> io::Result<PathBuf> {
+        let Some(path) = path.or_else(|| self.path.clone()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no file name"));
+        };
+        let contents = serde_json::to_string_pretty(&self.snapshot())?;
+        fs::write(&path, contents)?;
+        self.path = Some(path.clone());
+        Ok(path)
+    }
+
+    /// Loads the encounter from the given file, replacing the current encounter. This can be
+    /// undone.
+    pub fn load(&mut self, path: PathBuf) -> io::Result<()> {
+        let contents = fs::read_to_string(&path)?;
+        let snapshot = serde_json::from_str::<Snapshot>(&contents)?;
+        self.open(snapshot, format!("load {}", path.display()));
+        self.path = Some(path);
+        Ok(())
+    }
+
+    /// Replaces the current encounter with the given one. This can be undone.
+    pub fn open(&mut self, mut snapshot: Snapshot, description: impl Into<String>) {
+        // hand-written files may leave out identifiers, or repeat them
+        let mut ids = HashSet::new();
+        if !snapshot.combatants.iter().all(|c| ids.insert(c.id)) {
+            for (i, combatant) in snapshot.combatants.iter_mut().enumerate() {
+                combatant.id = CombatantId(i as u64);
+            }
+        }
+        if snapshot.turn.is_some_and(|turn| turn >= snapshot.combatants.len()) {
+            snapshot.turn = None;
+        }
+
+        self.record(description);
+        self.next_id = snapshot.combatants.iter()
+            .map(|c| c.id.0 + 1)
+            .max()
+            .unwrap_or_default()
+            .max(self.next_id);
+        self.restore(snapshot);
+    }
+
+    /// Records the current state of the encounter in the undo history. This must be called before
+    /// every change to the encounter, with a short description of the change.
+    pub fn record(&mut self, description: impl Into<String>) {
+        let snapshot = self.snapshot();
+        self.history.record(description.into(), snapshot);
+        self.revision += 1;
+    }
+
+    /// Undoes the most recent change to the encounter, returning its description.
+    pub fn undo(&mut self) -> Option<String> {
+        let (description, snapshot) = self.history.undo(self.snapshot())?;
+        self.restore(snapshot);
+        self.revision += 1;
+        self.log(format!("undo {}", description));
+        Some(description)
+    }
+
+    /// Redoes the most recently undone change to the encounter, returning its description.
+    pub fn redo(&mut self) -> Option<String> {
+        let (description, snapshot) = self.history.redo(self.snapshot())?;
+        self.restore(snapshot);
+        self.revision += 1;
+        self.log(format!("redo {}", description));
+        Some(description)
+    }
+
+    /// Returns the random number generator used for dice rolls.
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Returns the current revision of the encounter, which changes every time the encounter
+    /// changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the undo / redo history of the encounter.
+    pub fn history(&self) -> &History {
+        &self.history
     }
 
     /// Highlight the combatant at the given index.
     pub fn highlight(&mut self, idx: usize) {
-        self.highlighted = Some(idx);
+        self.table_state.select(Some(idx));
     }
 
     /// Remove the highlight on the currently highlighted combatant.
     pub fn unhighlight(&mut self) {
-        self.highlighted = None;
+        self.table_state.select(None);
+    }
+
+    /// Returns the index of the currently highlighted combatant.
+    pub fn highlighted(&self) -> Option<usize> {
+        self.table_state.selected()
+    }
+
+    /// Returns the index of the combatant whose turn it is, or [`None`] if combat has not started.
+    pub fn turn(&self) -> Option<usize> {
+        self.turn
+    }
+
+    /// Starts combat, sorting the combatants by initiative and giving the first turn of round 1 to
+    /// the combatant with the highest initiative.
+    pub fn start_combat(&mut self) {
+        if self.combatants.is_empty() {
+            return;
+        }
+
+        self.record("start combat");
+        self.sort();
+        self.turn = Some(0);
+        self.round = 1;
+        self.begin_turn();
+    }
+
+    /// Advances to the next combatant's turn, starting a new round after the last combatant.
+    ///
+    /// If combat has not started yet, this starts combat.
+    pub fn next_turn(&mut self) {
+        let Some(turn) = self.turn else {
+            self.start_combat();
+            return;
+        };
+
+        self.record("next turn");
+        self.end_turn(turn);
+
+        // the dead don't take turns
+        let len = self.combatants.len();
+        let mut next = turn;
+        for _ in 0..len {
+            next += 1;
+            if next >= len {
+                next = 0;
+                self.round += 1;
+            }
+            if !self.combatants[next].is_dead() {
+                break;
+            }
+        }
+        self.turn = Some(next);
+        if self.remove_dead {
+            self.remove_dead_monsters();
+        }
+        self.begin_turn();
+    }
+
+    /// Removes every dead monster from the initiative order, except the one whose turn it is.
+    /// This is part of advancing the turn, so it is undone along with it.
+    fn remove_dead_monsters(&mut self) {
+        let turn = self.turn.and_then(|turn| self.combatants.get(turn)).map(|c| c.id);
+        let dead = self.combatants.iter()
+            .filter(|c| c.kind == CombatantKind::Monster && c.is_dead() && Some(c.id) != turn)
+            .map(|c| (c.id, c.name().to_string()))
+            .collect::<Vec<_>>();
+        for (id, name) in dead {
+            self.combatants.retain(|c| c.id != id);
+            for combatant in &mut self.combatants {
+                combatant.remove_conditions_from(id);
+            }
+            self.log(format!("remove dead {}", name));
+        }
+        self.turn = turn.and_then(|id| self.combatants.iter().position(|c| c.id == id));
+    }
+
+    /// Performs the bookkeeping for the end of the given combatant's turn: durations of conditions
+    /// tied to that combatant's turn count down, and expire if necessary, and legendary creatures
+    /// with legendary actions left are reminded that they can take one.
+    fn end_turn(&mut self, idx: usize) {
+        let Some(ended) = self.combatants.get(idx).map(|c| c.id) else {
+            return;
+        };
+        for combatant in &mut self.combatants {
+            combatant.end_turn(ended);
+        }
+
+        let reminders = self.combatants.iter()
+            .filter(|c| c.id != ended && !c.is_dead())
+            .filter_map(|c| {
+                let legendary = c.legendary_actions().filter(|legendary| legendary.left() > 0)?;
+                Some(format!(
+                    "{} can take a legendary action ({} / {} left)",
+                    c.name(),
+                    legendary.left(),
+                    legendary.per_round,
+                ))
+            })
+            .collect::<Vec<_>>();
+        for reminder in reminders {
+            self.log(reminder);
+        }
+    }
+
+    /// Performs the bookkeeping for the start of the current combatant's turn: they regain all of
+    /// their actions, including their reaction, roll to recharge their spent abilities, and are
+    /// highlighted.
+    fn begin_turn(&mut self) {
+        self.table_state.select(self.turn);
+        let Some(combatant) = self.turn.and_then(|turn| self.combatants.get_mut(turn)) else {
+            return;
+        };
+        combatant.refresh_actions();
+
+        let name = combatant.name().to_string();
+        let mut entries = Vec::new();
+        for resource in combatant.resources.iter_mut().filter(|resource| resource.current == 0) {
+            let Some(min) = resource.recharge() else {
+                continue;
+            };
+            let roll = self.rng.roll(6);
+            let outcome = if roll >= u32::from(min) {
+                resource.current = resource.max;
+                "recharged"
+            } else {
+                "still spent"
+            };
+            entries.push(format!(
+                "{} rolls {} to recharge {} ({}): {}",
+                name,
+                roll,
+                resource.label(),
+                resource::format_recharge(min),
+                outcome,
+            ));
+        }
+        for entry in entries {
+            self.log(entry);
+        }
+    }
+
+    /// Goes back to the previous combatant's turn, going back a round before the first combatant.
+    /// Dead combatants are skipped, as they don't take turns.
+    ///
+    /// This does nothing on the first turn of combat.
+    pub fn previous_turn(&mut self) {
+        let Some(mut turn) = self.turn else {
+            return;
+        };
+        let mut round = self.round;
+        loop {
+            if turn == 0 {
+                if round <= 1 {
+                    return;
+                }
+                turn = self.combatants.len();
+                round -= 1;
+            }
+            turn -= 1;
+            if !self.combatants[turn].is_dead() {
+                break;
+            }
+        }
+        self.record("previous turn");
+        self.turn = Some(turn);
+        self.round = round;
+        self.table_state.select(self.turn);
     }
 
-    /// Render the tracker to a [`Table`] widget.
-    pub fn render(&self) -> Table {
-        Table::new(
+    /// Renders the combatant table to the given area, scrolling it so that the highlighted
+    /// combatant is visible.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let table = Table::new(
             self.combatants.iter()
                 .enumerate()
                 .map(|(i, combatant)| {
-                    let row = combatant.row().height(2);
-                    if Some(i) == self.highlighted {
-                        row.style(Style::default().bg(Color::Rgb(0, 48, 130)))
-                    } else {
-                        row
+                    let mut style = Style::default();
+                    if Some(i) == self.turn {
+                        style = style.fg(Color::LightGreen).bold();
                     }
+                    if combatant.is_dead() {
+                        style = style.fg(Color::DarkGray).add_modifier(Modifier::DIM);
+                    }
+                    combatant.row().height(2).style(style)
                 }),
             [
                 Constraint::Length(12), // initiative
                 Constraint::Fill(1),    // name
+                Constraint::Length(4),  // armor class
                 Constraint::Length(10), // actions
                 Constraint::Length(14), // hp / max hp
                 Constraint::Length(10), // temp hp
+                Constraint::Fill(1),    // conditions
             ],
         )
             .block(
@@ -86,18 +793,427 @@ impl Tracker {
                     .border_type(BorderType::Rounded)
                     .border_style(Style::default().fg(Color::White))
                     .padding(Padding::horizontal(1))
-                    .title("Initiative Tracker")
+                    .title(if self.round > 0 {
+                        format!("Initiative Tracker - Round {}", self.round)
+                    } else {
+                        "Initiative Tracker".to_string()
+                    })
             )
             .header(
                 Row::new([
                     Text::from("Initiative").centered(),
                     Text::from("Name").centered(),
+                    Text::from("AC").centered(),
                     Text::from("Actions").centered(),
                     Text::from("HP / Max HP").centered(),
                     Text::from("Temp HP").centered(),
+                    Text::from("Conditions").centered(),
                 ])
                     .bold()
                     .height(2)
             )
+            .highlight_style(Style::default().bg(Color::Rgb(0, 48, 130)));
+        frame.render_stateful_widget(table, area, &mut self.table_state);
+    }
+}
+
+/// Parses a name suffix used to number combatants, either a number ("3") or uppercase letters
+/// ("C", or "AA" after "Z").
+fn parse_suffix(suffix: &str) -> Option<u32> {
+    if let Ok(number) = suffix.parse() {
+        return Some(number);
+    }
+    if suffix.is_empty() || suffix.len() > 3 || !suffix.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(suffix.bytes().fold(0, |n, b| n * 26 + u32::from(b - b'A') + 1))
+}
+
+/// Returns the letter suffix for the given number: 1 is "A", 26 is "Z", and 27 is "AA".
+fn letter_suffix(mut n: u32) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(char::from(b'A' + (n % 26) as u8));
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{actions::Action, combatant::Concentration, condition::{AppliedCondition, Condition, Duration}};
+
+    fn combatant(name: &str, initiative: i32, modifier: i32, kind: CombatantKind) -> Combatant {
+        let mut combatant = Combatant::new(name.to_string(), 10, 10);
+        combatant.set_initiative(initiative);
+        combatant.initiative_modifier = modifier;
+        combatant.kind = kind;
+        combatant
+    }
+
+    fn names(tracker: &Tracker) -> Vec<&str> {
+        tracker.combatants().iter().map(|c| c.name()).collect()
+    }
+
+    #[test]
+    fn higher_initiative_goes_first() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Goblin", 8, 2, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Wolf", 12, 2, CombatantKind::Monster));
+        assert_eq!(names(&tracker), ["Ayla", "Wolf", "Goblin"]);
+    }
+
+    #[test]
+    fn ties_broken_by_modifier_then_players_first() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Ayla", 12, 2, CombatantKind::Player));
+        tracker.add_combatant(combatant("Bram", 12, 3, CombatantKind::Player));
+        assert_eq!(names(&tracker), ["Bram", "Ayla", "Goblin"]);
+    }
+
+    #[test]
+    fn roll_off_order_is_stable_across_additions() {
+        let mut tracker = Tracker::new();
+        for name in ["Goblin 1", "Goblin 2", "Goblin 3", "Goblin 4"] {
+            tracker.add_combatant(combatant(name, 12, 2, CombatantKind::Monster));
+        }
+        assert!(tracker.combatants().iter().all(|c| c.roll_off.is_some()));
+        let order = names(&tracker).join(",");
+        tracker.add_combatant(combatant("Wolf", 14, 2, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Bat", 3, 2, CombatantKind::Monster));
+        let tied = names(&tracker)[1..5].join(",");
+        assert_eq!(tied, order);
+    }
+
+    #[test]
+    fn lair_loses_ties() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(Combatant::lair());
+        tracker.add_combatant(combatant("Dragon", 20, -1, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Ayla", 21, 0, CombatantKind::Player));
+        assert_eq!(names(&tracker), ["Ayla", "Dragon", "Lair"]);
+        assert!(!tracker.move_in_ties(1, false));
+        assert!(!tracker.move_in_ties(2, true));
+    }
+
+    #[test]
+    fn lair_acts_on_initiative_twenty() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Goblin", 19, 2, CombatantKind::Monster));
+        tracker.add_combatant(Combatant::lair());
+        tracker.add_combatant(combatant("Dragon", 20, 0, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Ayla", 21, 0, CombatantKind::Player));
+        assert_eq!(names(&tracker), ["Ayla", "Dragon", "Lair", "Goblin"]);
+
+        // the lair keeps its place when a combatant later rolls 20
+        tracker.add_combatant(combatant("Bram", 20, 0, CombatantKind::Player));
+        assert_eq!(names(&tracker), ["Ayla", "Bram", "Dragon", "Lair", "Goblin"]);
+    }
+
+    #[test]
+    fn legendary_actions_are_regained_on_their_own_turn() {
+        let mut tracker = Tracker::new();
+        let mut dragon = combatant("Dragon", 20, 0, CombatantKind::Monster);
+        dragon.set_legendary_actions(3);
+        tracker.add_combatant(dragon);
+        tracker.add_combatant(combatant("Ayla", 21, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        let left = |tracker: &Tracker| tracker.combatants()[1].legendary_actions().map(|legendary| legendary.left());
+
+        tracker.next_turn();
+        assert!(tracker.spend_legendary_action(1));
+        assert!(tracker.spend_legendary_action(1));
+        assert_eq!(left(&tracker), Some(1));
+
+        // not on the dragon's own turn, which gives them all of them back
+        tracker.next_turn();
+        assert_eq!(left(&tracker), Some(3));
+        assert!(!tracker.spend_legendary_action(1));
+        assert_eq!(tracker.history().next_undo(), Some("next turn"));
+
+        tracker.next_turn();
+        for _ in 0..3 {
+            assert!(tracker.spend_legendary_action(1));
+        }
+        assert!(!tracker.spend_legendary_action(1));
+        assert!(!tracker.spend_legendary_action(2));
+    }
+
+    #[test]
+    fn chosen_order_is_kept_until_the_tie_is_joined() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 12, 2, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        assert!(tracker.move_in_ties(1, true));
+        assert_eq!(names(&tracker), ["Goblin", "Ayla"]);
+
+        // an unrelated combatant keeps the chosen order
+        tracker.add_combatant(combatant("Wolf", 12, 1, CombatantKind::Monster));
+        assert_eq!(names(&tracker), ["Goblin", "Ayla", "Wolf"]);
+
+        // a combatant joining the tie resets it to the usual rules
+        tracker.add_combatant(combatant("Bram", 12, 2, CombatantKind::Player));
+        assert_eq!(names(&tracker)[3], "Wolf");
+        assert!(tracker.combatants().iter().all(|c| c.tie_priority == 0));
+        let players = names(&tracker)[..2].to_vec();
+        assert!(players.contains(&"Ayla") && players.contains(&"Bram"));
+    }
+
+    #[test]
+    fn turns_skip_the_dead() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Wolf", 8, 2, CombatantKind::Monster));
+        tracker.next_turn();
+        tracker.combatant_mut(1).unwrap().take_damage(10, false);
+        assert!(tracker.combatants()[1].is_dead());
+
+        tracker.next_turn();
+        assert_eq!((tracker.turn(), tracker.round), (Some(2), 1));
+        tracker.previous_turn();
+        assert_eq!((tracker.turn(), tracker.round), (Some(0), 1));
+        tracker.previous_turn();
+        assert_eq!((tracker.turn(), tracker.round), (Some(0), 1));
+
+        tracker.next_turn();
+        tracker.next_turn();
+        assert_eq!((tracker.turn(), tracker.round), (Some(0), 2));
+        tracker.previous_turn();
+        assert_eq!((tracker.turn(), tracker.round), (Some(2), 1));
+    }
+
+    #[test]
+    fn suffixes_count_numbers_and_letters() {
+        assert_eq!(parse_suffix("3"), Some(3));
+        assert_eq!(parse_suffix("A"), Some(1));
+        assert_eq!(parse_suffix("Z"), Some(26));
+        assert_eq!(parse_suffix("AA"), Some(27));
+        assert_eq!(parse_suffix("BC"), Some(55));
+        assert_eq!(parse_suffix("b"), None);
+        assert_eq!(parse_suffix("Boss"), None);
+        assert_eq!(parse_suffix(""), None);
+
+        for n in [1, 26, 27, 52, 53, 702, 703] {
+            assert_eq!(parse_suffix(&letter_suffix(n)), Some(n));
+        }
+        assert_eq!([letter_suffix(26), letter_suffix(27), letter_suffix(702), letter_suffix(703)], ["Z", "AA", "ZZ", "AAA"]);
+    }
+
+    #[test]
+    fn group_names_continue_after_existing_copies() {
+        let mut tracker = Tracker::new();
+        assert_eq!(tracker.group_names("Goblin", 2, false), ["Goblin 1", "Goblin 2"]);
+
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Goblin Boss", 12, 2, CombatantKind::Monster));
+        assert_eq!(tracker.group_names("Goblin", 2, false), ["Goblin 2", "Goblin 3"]);
+
+        tracker.add_combatant(combatant("Goblin Y", 12, 2, CombatantKind::Monster));
+        assert_eq!(tracker.group_names("Goblin", 3, true), ["Goblin Z", "Goblin AA", "Goblin AB"]);
+        assert_eq!(tracker.next_name("Goblin Y"), "Goblin Z");
+        assert_eq!(tracker.next_name("Goblin"), "Goblin 26");
+        assert_eq!(tracker.next_name("Wolf"), "Wolf");
+    }
+
+    fn three_combatants() -> Tracker {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.add_combatant(combatant("Wolf", 8, 2, CombatantKind::Monster));
+        tracker
+    }
+
+    #[test]
+    fn removing_before_the_turn_keeps_the_turn() {
+        let mut tracker = three_combatants();
+        tracker.next_turn();
+        tracker.next_turn();
+        tracker.combatant_mut(1).unwrap().spend_action(Action::Action);
+        tracker.highlight(2);
+        tracker.remove_combatant(0);
+        assert_eq!(names(&tracker), ["Goblin", "Wolf"]);
+        assert_eq!((tracker.turn(), tracker.highlighted(), tracker.round), (Some(0), Some(1), 1));
+        // the Goblin's turn goes on rather than starting over
+        assert!(!tracker.combatants()[0].has_action(Action::Action));
+    }
+
+    #[test]
+    fn removing_on_their_turn_passes_it_on() {
+        let mut tracker = three_combatants();
+        tracker.next_turn();
+        tracker.next_turn();
+        tracker.combatant_mut(2).unwrap().spend_action(Action::Action);
+        tracker.remove_combatant(1);
+        assert_eq!((tracker.turn(), tracker.round), (Some(1), 1));
+        assert!(tracker.combatants()[1].has_action(Action::Action));
+    }
+
+    #[test]
+    fn removing_the_last_on_their_turn_starts_the_next_round() {
+        let mut tracker = three_combatants();
+        for _ in 0..3 {
+            tracker.next_turn();
+        }
+        tracker.highlight(1);
+        tracker.remove_combatant(2);
+        assert_eq!((tracker.turn(), tracker.highlighted(), tracker.round), (Some(0), Some(0), 2));
+
+        tracker.remove_combatant(0);
+        tracker.remove_combatant(0);
+        assert_eq!((tracker.turn(), tracker.highlighted()), (None, None));
+    }
+
+    /// Applies a condition with the given duration to the combatant at the given index.
+    fn add_condition(tracker: &mut Tracker, idx: usize, condition: Condition, duration: Duration) {
+        let applied = AppliedCondition { condition, duration: Some(duration), concentration: None };
+        tracker.combatant_mut(idx).unwrap().add_condition(applied);
+    }
+
+    fn has_condition(tracker: &Tracker, idx: usize, condition: Condition) -> bool {
+        tracker.combatants()[idx].condition(&condition).is_some()
+    }
+
+    #[test]
+    fn conditions_expire_after_rounds() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.next_turn();
+        add_condition(&mut tracker, 1, Condition::Poisoned, Duration::Rounds(2));
+
+        // the Goblin's first turn ends with one round left, its second ends the condition
+        tracker.next_turn();
+        tracker.next_turn();
+        assert!(has_condition(&tracker, 1, Condition::Poisoned));
+        tracker.next_turn();
+        tracker.next_turn();
+        assert!(!has_condition(&tracker, 1, Condition::Poisoned));
+    }
+
+    #[test]
+    fn conditions_expire_at_the_end_of_the_sources_next_turn() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.next_turn();
+        let source = tracker.combatants()[0].id;
+        add_condition(&mut tracker, 1, Condition::Frightened, Duration::EndOfSourceTurn { source, turns: 2 });
+
+        // applied during Ayla's turn, so it lasts through her next turn
+        tracker.next_turn();
+        tracker.next_turn();
+        assert!(has_condition(&tracker, 1, Condition::Frightened));
+        tracker.next_turn();
+        assert!(!has_condition(&tracker, 1, Condition::Frightened));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.next_turn();
+        tracker.next_turn();
+        tracker.next_turn();
+        tracker.deal_damage(1, &[damage(4)], false);
+        let goblin = tracker.combatant_mut(1).unwrap();
+        goblin.grant_temp_hit_points(3);
+        goblin.spend_action(Action::Reaction);
+        add_condition(&mut tracker, 1, Condition::Prone, Duration::Rounds(2));
+
+        let path = std::env::temp_dir().join(format!("initiative-tracker-test-{}.json", std::process::id()));
+        tracker.save(Some(path.clone())).unwrap();
+        let mut loaded = Tracker::new();
+        let result = loaded.load(path.clone());
+        fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        assert_eq!(names(&loaded), ["Ayla", "Goblin"]);
+        assert_eq!((loaded.turn(), loaded.round), (Some(0), 2));
+        let goblin = &loaded.combatants()[1];
+        assert_eq!((goblin.hit_points(), goblin.temp_hit_points()), (6, 3));
+        assert!(goblin.has_action(Action::Action));
+        assert!(!goblin.has_action(Action::Reaction));
+        assert_eq!(goblin.conditions(), tracker.combatants()[1].conditions());
+    }
+
+    fn damage(amount: i32) -> DamageInstance {
+        DamageInstance { amount, kind: None, magical: false }
+    }
+
+    #[test]
+    fn undo_and_redo_damage() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.record("damage Goblin");
+        tracker.deal_damage(0, &[damage(4)], false);
+        assert_eq!(tracker.combatants()[0].hit_points(), 6);
+
+        assert_eq!(tracker.undo().as_deref(), Some("damage Goblin"));
+        assert_eq!(tracker.combatants()[0].hit_points(), 10);
+        assert_eq!(tracker.redo().as_deref(), Some("damage Goblin"));
+        assert_eq!(tracker.combatants()[0].hit_points(), 6);
+    }
+
+    #[test]
+    fn undo_and_redo_sort() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 12, 2, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        assert!(tracker.move_in_ties(1, true));
+        assert_eq!(names(&tracker), ["Goblin", "Ayla"]);
+
+        tracker.undo();
+        assert_eq!(names(&tracker), ["Ayla", "Goblin"]);
+        tracker.redo();
+        assert_eq!(names(&tracker), ["Goblin", "Ayla"]);
+    }
+
+    #[test]
+    fn undo_and_redo_turns() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 15, 0, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        tracker.next_turn();
+        tracker.next_turn();
+        tracker.next_turn();
+        assert_eq!((tracker.turn(), tracker.round), (Some(0), 2));
+
+        tracker.undo();
+        assert_eq!((tracker.turn(), tracker.round), (Some(1), 1));
+        tracker.undo();
+        tracker.undo();
+        assert_eq!((tracker.turn(), tracker.round), (None, 0));
+        tracker.redo();
+        assert_eq!((tracker.turn(), tracker.round), (Some(0), 1));
+    }
+
+    #[test]
+    fn undo_drops_pending_concentration_checks() {
+        let mut tracker = Tracker::new();
+        let mut caster = combatant("Ayla", 15, 0, CombatantKind::Player);
+        caster.concentration = Some(Concentration { spell: "bless".to_string(), targets: Vec::new() });
+        tracker.add_combatant(caster);
+        tracker.record("damage Ayla");
+        tracker.deal_damage(0, &[damage(3)], false);
+        assert!(tracker.has_concentration_checks());
+
+        tracker.undo();
+        assert!(!tracker.has_concentration_checks());
+        assert_eq!(tracker.next_concentration_check(), None);
+    }
+
+    #[test]
+    fn cannot_reorder_across_modifiers() {
+        let mut tracker = Tracker::new();
+        tracker.add_combatant(combatant("Ayla", 12, 3, CombatantKind::Player));
+        tracker.add_combatant(combatant("Goblin", 12, 2, CombatantKind::Monster));
+        assert!(!tracker.move_in_ties(1, true));
+        assert_eq!(names(&tracker), ["Ayla", "Goblin"]);
     }
 }
@@ -1,6 +1,5 @@
 use crate::{state::State, tracker::Tracker};
 use crossterm::{
-    cursor,
     event,
     terminal,
     execute,
@@ -15,6 +14,10 @@ pub struct RenderLocations {
     /// at the top of the screen.
     pub combatant_table: Rect,
 
+    /// The box showing the event log, newest entry first. This appears at the top-right, next to
+    /// the combatant table.
+    pub log: Rect,
+
     /// The box showing the available commands for the current state. This appears at the
     /// bottom-left.
     pub guide: Rect,
@@ -59,6 +62,11 @@ impl Ui {
                     .horizontal_margin(1)
                     .vertical_margin(1)
                     .split(size);
+                let table_layout = Layout::horizontal([
+                    Constraint::Percentage(70),
+                    Constraint::Percentage(30),
+                ])
+                    .split(full_layout[0]);
                 let state_layout = Layout::horizontal([
                     Constraint::Percentage(50),
                     Constraint::Percentage(50),
@@ -70,7 +78,8 @@ impl Ui {
                 ])
                     .split(state_layout[1]);
                 RenderLocations {
-                    combatant_table: full_layout[0],
+                    combatant_table: table_layout[0],
+                    log: table_layout[1],
                     guide: state_layout[0],
                     state: input_layout[0],
                     input: input_layout[1],
@@ -79,27 +88,31 @@ impl Ui {
         })
     }
 
-    /// Reads a character from the terminal.
-    pub fn read_char(&self) -> io::Result<char> {
-        loop {
-            match event::read()? {
-                event::Event::Key(event) => {
-                    match event.code {
-                        event::KeyCode::Char(c) => break Ok(c),
-                        _ => (),
-                    }
-                },
-                _ => (),
-            }
-        }
-    }
-
     /// Renders the initiative tracker to the terminal.
-    pub fn render(&mut self, tracker: &Tracker, state: &State) -> io::Result<()> {
+    pub fn render(&mut self, tracker: &mut Tracker, state: &State) -> io::Result<()> {
         self.terminal.draw(|f| {
-            f.render_widget(tracker.render(), self.locations.combatant_table);
+            tracker.render(f, self.locations.combatant_table);
+            f.render_widget(
+                Paragraph::new(
+                    tracker.log_entries()
+                        .iter()
+                        .rev()
+                        .map(|entry| Line::from(entry.as_str()))
+                        .collect::<Vec<_>>()
+                )
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::bordered()
+                            .border_type(BorderType::Rounded)
+                            .border_style(Style::default().fg(Color::White))
+                            .padding(Padding::horizontal(1))
+                            .title("Log"),
+                    ),
+                self.locations.log,
+            );
             f.render_widget(
-                Paragraph::new(state.help())
+                Paragraph::new(state.help(tracker))
+                    .wrap(Wrap { trim: false })
                     .block(
                         Block::bordered()
                             .border_type(BorderType::Rounded)
@@ -110,10 +123,14 @@ impl Ui {
                 self.locations.guide,
             );
 
-            if let Some((widget, input)) = state.render() {
-                f.render_widget(widget, self.locations.state);
-                if let Some(input) = input {
-                    f.render_widget(input, self.locations.input);
+            if let Some((widget, input)) = state.render(tracker) {
+                match input {
+                    Some(input) => {
+                        f.render_widget(widget, self.locations.state);
+                        f.render_widget(input, self.locations.input);
+                    },
+                    // states without input use the input box as well
+                    None => f.render_widget(widget, self.locations.state.union(self.locations.input)),
                 }
             } else {
                 f.render_widget(
@@ -132,14 +149,17 @@ impl Ui {
 }
 
 /// Cleans up the terminal.
+///
+/// Errors are ignored, since this may run while unwinding from a panic, and a second panic would
+/// abort the program before the panic message is printed.
 impl Drop for Ui {
     fn drop(&mut self) {
-        self.terminal.show_cursor().unwrap();
-        execute!(
+        let _ = self.terminal.show_cursor();
+        let _ = execute!(
             io::stdout(),
             event::PopKeyboardEnhancementFlags,
             terminal::LeaveAlternateScreen,
-        ).unwrap();
-        terminal::disable_raw_mode().unwrap();
+        );
+        let _ = terminal::disable_raw_mode();
     }
 }